@@ -0,0 +1,135 @@
+//! Benchmarks of the sfc fast paths and the vectorized constructors against the
+//! generic, per-element code paths that they replace.
+//!
+//! Run with `cargo bench` and `cargo bench --features parallel`. R must be
+//! installed as the inputs are created by an embedded R session.
+use criterion::{criterion_group, criterion_main, Criterion};
+use extendr_api::prelude::*;
+use sfconversions::{constructors::*, fromsf::{sfc_to_geoms, SfcIter}, Geom};
+
+// a list of sfg `x` as both its homogeneous sfc class and an sfc_GEOMETRY
+fn sfcs(x: &str, cls: &str) -> (List, List) {
+    let x = eval_string(x).unwrap();
+    let sfc = |cls: &str| List::try_from(x.clone().set_class([cls, "sfc"]).unwrap()).unwrap();
+    (sfc(cls), sfc("sfc_GEOMETRY"))
+}
+
+// 50,000 road segments with between 2 and 100 vertices each
+fn road_network(c: &mut Criterion) {
+    let (fast, generic) = sfcs(
+        "set.seed(0)
+        lapply(sample(2:100, 50000, replace = TRUE), function(n) {
+            structure(matrix(cumsum(runif(n * 2)), ncol = 2), class = c('XY', 'LINESTRING', 'sfg'))
+        })",
+        "sfc_LINESTRING",
+    );
+
+    let mut group = c.benchmark_group("road network");
+    group.bench_function("sfc_LINESTRING", |b| b.iter(|| sfc_to_geoms(fast.clone())));
+    group.bench_function("sfc_GEOMETRY", |b| b.iter(|| sfc_to_geoms(generic.clone())));
+    group.finish();
+}
+
+// 20,000 parcels each with a hole, as polygons and as 2 part multipolygons
+fn parcels(c: &mut Criterion) {
+    let polys = "set.seed(1)
+        lapply(1:20000, function(i) {
+            x <- runif(1, 0, 1000)
+            y <- runif(1, 0, 1000)
+            structure(list(
+                matrix(c(x, x + 10, x + 10, x, x, y, y, y + 10, y + 10, y), ncol = 2),
+                matrix(c(x + 2, x + 4, x + 4, x + 2, x + 2, y + 2, y + 2, y + 4, y + 4, y + 2), ncol = 2)
+            ), class = c('XY', 'POLYGON', 'sfg'))
+        })";
+
+    let (fast, generic) = sfcs(polys, "sfc_POLYGON");
+
+    let mut group = c.benchmark_group("parcels");
+    group.bench_function("sfc_POLYGON", |b| b.iter(|| sfc_to_geoms(fast.clone())));
+    group.bench_function("sfc_GEOMETRY", |b| b.iter(|| sfc_to_geoms(generic.clone())));
+    group.finish();
+
+    let (fast, generic) = sfcs(
+        &format!("p <- {polys}
+        lapply(seq(1, length(p), by = 2), function(i) {{
+            structure(lapply(p[i:(i + 1)], unclass), class = c('XY', 'MULTIPOLYGON', 'sfg'))
+        }})"),
+        "sfc_MULTIPOLYGON",
+    );
+
+    let mut group = c.benchmark_group("multipolygon layer");
+    group.bench_function("sfc_MULTIPOLYGON", |b| b.iter(|| sfc_to_geoms(fast.clone())));
+    group.bench_function("sfc_GEOMETRY", |b| b.iter(|| sfc_to_geoms(generic.clone())));
+
+    // with the `parallel` feature `sfc_to_geoms()` builds the geometries with rayon
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            SfcIter::new(generic.clone())
+                .map(|geom| geom.ok().flatten())
+                .collect::<Vec<Option<Geom>>>()
+        })
+    });
+    group.finish();
+}
+
+// 1,000,000 points from two columns against one `geom_point()` call per row
+fn points(c: &mut Criterion) {
+    let x = Doubles::from_values((0..1_000_000).map(|i| i as f64));
+    let y = Doubles::from_values((0..1_000_000).map(|i| -i as f64));
+
+    let mut group = c.benchmark_group("points");
+    group.bench_function("geom_points", |b| b.iter(|| geom_points(x.clone(), y.clone()).unwrap()));
+    group.bench_function("geom_point", |b| {
+        b.iter(|| {
+            x.iter()
+                .zip(y.iter())
+                .map(|(x, y)| geom_point(x.inner(), y.inner()))
+                .collect::<List>()
+        })
+    });
+    group.finish();
+}
+
+// 100,000 parcels as lists of rings against one `geom_polygon()` call per parcel
+fn polygons(c: &mut Criterion) {
+    let x = List::try_from(
+        eval_string(
+            "set.seed(3)
+            lapply(1:100000, function(i) {
+                x <- runif(1, 0, 1000)
+                y <- runif(1, 0, 1000)
+                list(matrix(c(x, x + 10, x + 10, x, x, y, y, y + 10, y + 10, y), ncol = 2))
+            })",
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let opts = GeomOptions::default();
+
+    let mut group = c.benchmark_group("polygons");
+    group.bench_function("geom_polygons", |b| b.iter(|| geom_polygons(x.clone(), &opts).unwrap()));
+    group.bench_function("geom_polygon", |b| {
+        b.iter(|| {
+            x.iter()
+                .map(|(_, rings)| geom_polygon(List::try_from(rings).unwrap()))
+                .collect::<List>()
+        })
+    });
+    group.finish();
+}
+
+fn setup(c: &mut Criterion) {
+    extendr_engine::start_r();
+    road_network(c);
+    parcels(c);
+    points(c);
+    polygons(c);
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = setup
+}
+criterion_main!(benches);
@@ -2,10 +2,83 @@
 //! 
 //! These function are used to convert R objects into geo-types geometry.
 //! These functions mimic the structure of sfg objects from the sf package. 
+//! Coordinate matrices may be double or integer matrices. Wherever a coordinate
+//! matrix is accepted a data.frame with 2 numeric columns can be used instead.
+//! Non-finite coordinates are an error unless `GeomOptions::na_policy` says
+//! otherwise.
+//! Matrices with column names `x` and `y` have their columns selected by name.
 //! Additional quality of life constructors are made available in {rsgeo}.
 use extendr_api::prelude::*;
-use geo_types::{coord, Coord, LineString, Point, Polygon, point, MultiLineString, MultiPoint, MultiPolygon};
-use crate::Geom;
+use geo_types::{coord, Coord, LineString, Point, Polygon, Rect, MultiLineString, MultiPoint, MultiPolygon};
+use geo::orient::{Direction, Orient};
+use crate::{Geom, fromdf::close_ring, fromsf::bbox_to_rect, vctrs::{as_rsgeo_vctr, determine_geoms_class}};
+
+/// Options for the constructors that read coordinate matrices. The defaults are
+/// strict so that invalid geometries are caught where they are built. Use struct
+/// update syntax to change only some of them.
+///
+/// ```
+/// let opts = GeomOptions { dedup: true, ..Default::default() };
+/// let lns = geom_linestrings(x, &opts)?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeomOptions {
+    /// how non-finite coordinates are handled. Default `NaPolicy::Error`
+    pub na_policy: NaPolicy,
+    /// use the first 2 columns of a matrix with more than 2 columns as the x and
+    /// y coordinates and ignore the rest, e.g. the z and time columns of GPS
+    /// exports. Matrices must have exactly 2 columns otherwise. Default `false`
+    pub xy_only: bool,
+    /// accept a linestring, or a component of a multilinestring, with a single
+    /// coordinate e.g. when assembling geometries incrementally. Default `false`
+    pub allow_degenerate: bool,
+    /// close polygon rings whose first and last coordinates differ, as sf does.
+    /// Unclosed rings are an error otherwise. Default `true`
+    pub auto_close: bool,
+    /// check that each polygon ring has at least 3 distinct coordinates and that
+    /// a polygon has at least 1 ring. Unchecked rings are used as is and a polygon
+    /// without rings is empty. Default `true`
+    pub validate_rings: bool,
+    /// orient polygon rings following the OGC convention: the exterior ring is
+    /// counter-clockwise and interior rings are clockwise. Default `false` which
+    /// keeps rings as provided
+    pub orient: bool,
+    /// remove consecutive duplicate coordinates as they are read, see
+    /// `dedup_coords()`. The number removed is set as the `n_dropped` attribute
+    /// of the result. Default `false`
+    pub dedup: bool,
+}
+
+impl Default for GeomOptions {
+    fn default() -> Self {
+        GeomOptions {
+            na_policy: NaPolicy::Error,
+            xy_only: false,
+            allow_degenerate: false,
+            auto_close: true,
+            validate_rings: true,
+            orient: false,
+            dedup: false,
+        }
+    }
+}
+
+impl GeomOptions {
+    /// Options for reading sfg objects. sf has already validated them when they
+    /// were created so coordinates, including `NA`, degenerate linestrings, and
+    /// polygon rings are taken as is.
+    pub const fn lenient() -> Self {
+        GeomOptions {
+            na_policy: NaPolicy::Keep,
+            xy_only: false,
+            allow_degenerate: true,
+            auto_close: true,
+            validate_rings: false,
+            orient: false,
+            dedup: false,
+        }
+    }
+}
 
 // TODO REMOVE SCALAR CLASSES 
 /// Create a single `point` from an x and y value.
@@ -16,143 +89,1675 @@ pub fn geom_point(x: f64, y: f64) -> Robj {
         .unwrap()
 }
 
-/// Create a single `multipoint` from a 2 dimensional matrix.
-pub fn geom_multipoint(x: RArray<f64, [usize; 2]>) -> Robj {
-    let mpnt = MultiPoint::new(matrix_to_points(x));
-    Geom::from(mpnt)
+/// Create an `rs_POINT` vector from vectors of x and y coordinates.
+/// `x` and `y` must be the same length. If either coordinate of a pair is `NA`
+/// the point is empty and is returned to R as `c(NA, NA)`. Zero length inputs
+/// return an empty `rs_POINT` vector.
+pub fn geom_points(x: Doubles, y: Doubles) -> Result<Robj> {
+    let n = x.len();
+
+    if y.len() != n {
+        return Err(Error::Other(format!(
+            "`x` and `y` must be the same length. Found {} and {}.",
+            n,
+            y.len()
+        )));
+    }
+
+    let mut res = List::new(n);
+
+    for (i, (xi, yi)) in x.iter().zip(y.iter()).enumerate() {
+        let pnt = if xi.is_na() || yi.is_na() {
+            let na = Rfloat::na().inner();
+            Point::new(na, na)
+        } else {
+            Point::new(xi.inner(), yi.inner())
+        };
+
+        let pnt = Geom::from(pnt)
+            .into_robj()
+            .set_class(["point", "Geom"])?;
+
+        res.set_elt(i, pnt)?;
+    }
+
+    as_rsgeo_vctr(res, "point")
+}
+
+/// Create a single `multipoint` from a 2 dimensional matrix or a list of points.
+/// See `multipoint_to_points()`. A zero row matrix creates an empty multipoint.
+/// Panics if `x` cannot be converted, see `geom_multipoint_checked()`.
+pub fn geom_multipoint(x: impl Into<Robj>) -> Robj {
+    match geom_multipoint_checked(x.into(), &GeomOptions::default()) {
+        Ok(mpnt) => mpnt,
+        Err(e) => panic!("{e}"),
+    }
+}
+
+/// Like `geom_multipoint()` but returns an error instead of panicking. See
+/// `GeomOptions`.
+pub fn geom_multipoint_checked(x: Robj, opts: &GeomOptions) -> Result<Robj> {
+    let mut pnts = multipoint_to_points(&x, opts)?;
+    let mut dedup = Dedup::new(opts.dedup);
+
+    if opts.dedup {
+        let coords = dedup.apply(pnts.into_iter().map(|pnt| pnt.0).collect());
+        pnts = coords.into_iter().map(Point::from).collect();
+    }
+
+    let mpnt = Geom::from(MultiPoint::new(pnts))
+        .into_robj()
+        .set_class(["multipoint", "Geom"])?;
+
+    dedup.annotate(mpnt)
+}
+
+/// Create an `rs_MULTIPOINT` vector from a list of 2 dimensional matrices. `NULL`
+/// elements are missing geometries and zero row matrices are empty multipoints.
+/// Errors with the position of the first element that is not a numeric matrix
+/// with 2 columns. See `GeomOptions`.
+pub fn geom_multipoints(x: List, opts: &GeomOptions) -> Result<Robj> {
+    let mut res = List::new(x.len());
+
+    for (i, (_, xi)) in x.into_iter().enumerate() {
+        if xi.is_null() {
+            continue;
+        }
+
+        let pnts = element_coords(xi, i, opts)?.into_iter().map(Point::from).collect::<Vec<Point>>();
+        let mpnt = Geom::from(MultiPoint::new(pnts))
+            .into_robj()
+            .set_class(["multipoint", "Geom"])?;
+
+        res.set_elt(i, mpnt)?;
+    }
+
+    as_rsgeo_vctr(res, "multipoint")
+}
+
+/// Create a single `multipoint` from vectors of x and y coordinates. `x` and `y`
+/// must be the same length. Pairs where either coordinate is `NA` are dropped as
+/// an sf MULTIPOINT cannot contain empty points.
+pub fn geom_multipoint_xy(x: Doubles, y: Doubles) -> Result<Robj> {
+    if x.len() != y.len() {
+        return Err(Error::Other(format!(
+            "`x` and `y` must be the same length. Found {} and {}.",
+            x.len(),
+            y.len()
+        )));
+    }
+
+    let pnts = x
+        .iter()
+        .zip(y.iter())
+        .filter(|(xi, yi)| !xi.is_na() && !yi.is_na())
+        .map(|(xi, yi)| Point::new(xi.inner(), yi.inner()))
+        .collect::<Vec<Point>>();
+
+    Geom::from(MultiPoint::new(pnts))
         .into_robj()
         .set_class(["multipoint", "Geom"])
-        .unwrap()
 }
 
-/// Create a single `linestring` from a 2 dimensional matrix.
-pub fn geom_linestring(x: RArray<f64, [usize; 2]>) -> Robj {
-    let coords = matrix_to_coords(x);
-    let lns = LineString::new(coords);
-    Geom::from(lns)
+/// Create a single `linestring` from a 2 dimensional matrix or data.frame. A zero
+/// row matrix creates an empty linestring. Panics if there is a single coordinate
+/// or `x` cannot be converted, see `geom_linestring_checked()`.
+pub fn geom_linestring(x: impl Into<Robj>) -> Robj {
+    match geom_linestring_checked(x.into(), &GeomOptions::default()) {
+        Ok(lns) => lns,
+        Err(e) => panic!("{e}"),
+    }
+}
+
+/// Like `geom_linestring()` but returns an error instead of panicking. A linestring
+/// with a single coordinate is an error unless `opts.allow_degenerate` is true.
+/// See `GeomOptions`.
+pub fn geom_linestring_checked(x: Robj, opts: &GeomOptions) -> Result<Robj> {
+    let mut dedup = Dedup::new(opts.dedup);
+    let coords = dedup.apply(robj_coords(&x, "`x`", opts)?);
+    let lns = linestring_inner(coords, "`x`", opts.allow_degenerate)?;
+
+    let lns = Geom::from(lns)
         .into_robj()
-        .set_class(["linestring", "Geom"])
-        .unwrap()
+        .set_class(["linestring", "Geom"])?;
+
+    dedup.annotate(lns)
+}
+
+/// Create an `rs_LINESTRING` vector from a list of 2 dimensional matrices. `NULL`
+/// elements are missing geometries. Errors with the position of the first element
+/// that is not a numeric matrix with 2 columns or, unless `opts.allow_degenerate`
+/// is true, has a single coordinate. With `opts.dedup` the `n_dropped` attribute
+/// counts the coordinates removed from all elements.
+pub fn geom_linestrings(x: List, opts: &GeomOptions) -> Result<Robj> {
+    let mut dedup = Dedup::new(opts.dedup);
+    let mut res = List::new(x.len());
+
+    for (i, (_, xi)) in x.into_iter().enumerate() {
+        if xi.is_null() {
+            continue;
+        }
+
+        let what = format!("Element {}", i + 1);
+        let lns = linestring_inner(dedup.apply(element_coords(xi, i, opts)?), &what, opts.allow_degenerate)?;
+        let lns = Geom::from(lns)
+            .into_robj()
+            .set_class(["linestring", "Geom"])?;
+
+        res.set_elt(i, lns)?;
+    }
+
+    dedup.annotate(as_rsgeo_vctr(res, "linestring")?)
+}
+
+/// Create an `rs_LINESTRING` vector from vectors of x and y coordinates and an `id`
+/// which groups them. Each contiguous run of identical ids creates one linestring
+/// in the order of the input. `id` must be sorted and cannot contain `NA`.
+pub fn geom_linestrings_xy(x: Doubles, y: Doubles, id: Integers) -> Result<Robj> {
+    let n = x.len();
+
+    if y.len() != n || id.len() != n {
+        return Err(Error::Other(format!(
+            "`x`, `y`, and `id` must be the same length. Found {}, {}, and {}.",
+            n,
+            y.len(),
+            id.len()
+        )));
+    }
+
+    let mut lines: Vec<LineString> = Vec::new();
+    let mut coords: Vec<Coord> = Vec::new();
+    let mut current: Option<i32> = None;
+
+    for i in 0..n {
+        if id[i].is_na() {
+            return Err(Error::Other(format!("`id` cannot be `NA`. Found `NA` at position {}.", i + 1)));
+        }
+
+        let idi = id[i].inner();
+
+        if let Some(cur) = current {
+            if idi < cur {
+                return Err(Error::Other(format!("`id` must be sorted. Found unsorted `id` at position {}.", i + 1)));
+            }
+
+            if idi != cur {
+                lines.push(LineString::new(std::mem::take(&mut coords)));
+            }
+        }
+
+        current = Some(idi);
+        coords.push(coord! {x: x[i].inner(), y: y[i].inner()});
+    }
+
+    if current.is_some() {
+        lines.push(LineString::new(coords));
+    }
+
+    let mut res = List::new(lines.len());
+
+    for (i, lns) in lines.into_iter().enumerate() {
+        let lns = Geom::from(lns)
+            .into_robj()
+            .set_class(["linestring", "Geom"])?;
+        res.set_elt(i, lns)?;
+    }
+
+    as_rsgeo_vctr(res, "linestring")
 }
 
+/// Create an `rs_LINESTRING` vector of two point segments from the start and end
+/// coordinates in `x0`, `y0`, `x1`, and `y1`, e.g. origin-destination data. All
+/// four vectors must be the same length. A row with any `NA` is a missing geometry.
+/// Not to be confused with `ops::geom_segments()` which splits a geometry into its
+/// segments.
+pub fn geom_segments_xy(x0: Doubles, y0: Doubles, x1: Doubles, y1: Doubles) -> Result<Robj> {
+    let n = x0.len();
+
+    let mismatched = [("y0", y0.len()), ("x1", x1.len()), ("y1", y1.len())]
+        .into_iter()
+        .find(|(_, len)| *len != n);
+
+    if let Some((name, len)) = mismatched {
+        return Err(Error::Other(format!(
+            "`x0` and `{name}` must be the same length. Found {n} and {len}."
+        )));
+    }
+
+    let mut res = List::new(n);
+
+    for i in 0..n {
+        let crds = [x0[i], y0[i], x1[i], y1[i]];
+
+        if crds.iter().any(|crd| crd.is_na()) {
+            continue;
+        }
+
+        let lns = LineString::new(vec![
+            coord! {x: crds[0].inner(), y: crds[1].inner()},
+            coord! {x: crds[2].inner(), y: crds[3].inner()},
+        ]);
+
+        let lns = Geom::from(lns)
+            .into_robj()
+            .set_class(["linestring", "Geom"])?;
 
-/// Create a single `multilinestring` from a list of 2 dimensional matrices.
+        res.set_elt(i, lns)?;
+    }
+
+    as_rsgeo_vctr(res, "linestring")
+}
+
+/// Create a single `multilinestring` from a list of 2 dimensional matrices. Panics
+/// if a component has a single coordinate.
 pub fn geom_multilinestring(x: List) -> Robj {
+    match geom_multilinestring_checked(x, &GeomOptions::default()) {
+        Ok(mlns) => mlns,
+        Err(e) => panic!("{e}"),
+    }
+}
+
+/// Like `geom_multilinestring()` but returns an error instead of panicking. A
+/// component with a single coordinate is an error unless `opts.allow_degenerate`
+/// is true. See `GeomOptions`.
+pub fn geom_multilinestring_checked(x: List, opts: &GeomOptions) -> Result<Robj> {
+    let mut dedup = Dedup::new(opts.dedup);
+
     let vec_lns = x
         .into_iter()
-        .map(|(_, x)| LineString::new(
-            matrix_to_coords(
-                RMatrix::try_from(x).unwrap()
-            ))
-        )
-        .collect::<Vec<LineString>>();
-
-    Geom::from(MultiLineString::new(vec_lns))
+        .enumerate()
+        .map(|(i, (_, x))| {
+            let what = format!("Component {}", i + 1);
+            linestring_inner(dedup.apply(robj_coords(&x, &what, opts)?), &what, opts.allow_degenerate)
+        })
+        .collect::<Result<Vec<LineString>>>()?;
+
+    let mlns = Geom::from(MultiLineString::new(vec_lns))
         .into_robj()
-        .set_class(["multilinestring", "Geom"])
-        .unwrap()
+        .set_class(["multilinestring", "Geom"])?;
+
+    dedup.annotate(mlns)
 }
 
-/// Create a single `polygon` from a list of 2 dimensional matrices.
+/// Create an `rs_MULTILINESTRING` vector from a list where each element is a list
+/// of 2 dimensional matrices, one per component linestring. A single matrix is
+/// accepted as a multilinestring with one component. `NULL` elements are missing
+/// geometries. Errors with the position of the feature and component that could
+/// not be converted or, unless `opts.allow_degenerate` is true, has a single
+/// coordinate. See `GeomOptions`.
+pub fn geom_multilinestrings(x: List, opts: &GeomOptions) -> Result<Robj> {
+    let mut dedup = Dedup::new(opts.dedup);
+    let mut res = List::new(x.len());
+
+    for (i, (_, xi)) in x.into_iter().enumerate() {
+        if xi.is_null() {
+            continue;
+        }
+
+        let lines = if is_coords(&xi) {
+            vec![xi]
+        } else {
+            List::try_from(xi)
+                .map_err(|_| {
+                    Error::Other(format!(
+                        "Feature {} must be a matrix or a list of matrices",
+                        i + 1
+                    ))
+                })?
+                .values()
+                .collect::<Vec<Robj>>()
+        };
+
+        let lines = lines
+            .into_iter()
+            .enumerate()
+            .map(|(j, line)| {
+                let what = format!("Component {} of feature {}", j + 1, i + 1);
+                linestring_inner(dedup.apply(robj_coords(&line, &what, opts)?), &what, opts.allow_degenerate)
+            })
+            .collect::<Result<Vec<LineString>>>()?;
+
+        let mlns = Geom::from(MultiLineString::new(lines))
+            .into_robj()
+            .set_class(["multilinestring", "Geom"])?;
+
+        res.set_elt(i, mlns)?;
+    }
+
+    dedup.annotate(as_rsgeo_vctr(res, "multilinestring")?)
+}
+
+/// Create a single `polygon` from a list of 2 dimensional matrices. Panics with
+/// the ring that could not be converted, see `geom_polygon_checked()`.
 pub fn geom_polygon(x: List) -> Robj {
-    let n = x.len();
+    match geom_polygon_checked(x, &GeomOptions::default()) {
+        Ok(poly) => poly,
+        Err(e) => panic!("{e}"),
+    }
+}
+
+/// Create a single `polygon` from a list of 2 dimensional matrices with OGC ring
+/// orientation: the exterior ring is counter-clockwise and interior rings are clockwise.
+pub fn geom_polygon_oriented(x: List) -> Robj {
+    match geom_polygon_checked(x, &GeomOptions { orient: true, ..Default::default() }) {
+        Ok(poly) => poly,
+        Err(e) => panic!("{e}"),
+    }
+}
+
+/// Create a single `polygon` from a list of 2 dimensional matrices. Each ring must
+/// have at least 3 distinct coordinates. Rings whose first and last coordinates
+/// differ are closed if `opts.auto_close` is true, as `geom_polygon()` does, and
+/// are an error otherwise. Errors name the ring that is invalid. With `opts.dedup`
+/// ring closing coordinates are never removed. See `GeomOptions`.
+pub fn geom_polygon_checked(x: List, opts: &GeomOptions) -> Result<Robj> {
+    let mut dedup = Dedup::new(opts.dedup);
+
+    let poly = Geom::from(polygon_inner(x, opts, &mut dedup)?)
+        .into_robj()
+        .set_class(["polygon", "Geom"])?;
 
-    let mut linestrings: Vec<LineString> = Vec::with_capacity(n);
+    dedup.annotate(poly)
+}
 
-    let exterior = matrix_to_coords(x[0].as_matrix().unwrap());
-    let exterior = LineString::new(exterior);
+/// Create an `rs_POLYGON` vector from a list where each element is a list of
+/// 2 dimensional matrices, the first being the exterior ring. `NULL` elements are
+/// missing geometries. Errors with the position of the feature and ring that
+/// could not be converted. See `GeomOptions`.
+pub fn geom_polygons(x: List, opts: &GeomOptions) -> Result<Robj> {
+    let mut dedup = Dedup::new(opts.dedup);
+    let mut res = List::new(x.len());
 
-    if n > 1 {
-        for i in 1..n {
-            let xi: RMatrix<f64> = x[i].to_owned().try_into().unwrap();
-            let coords = matrix_to_coords(xi);
-            let line = LineString::new(coords);
-            linestrings.push(line);
+    for (i, (_, xi)) in x.into_iter().enumerate() {
+        if xi.is_null() {
+            continue;
         }
+
+        let poly = List::try_from(xi)
+            .map_err(|_| Error::Other("must be a list of matrices".to_string()))
+            .and_then(|rings| polygon_inner(rings, opts, &mut dedup))
+            .map_err(|e| Error::Other(format!("Failed to create feature {}: {e}", i + 1)))?;
+
+        let poly = Geom::from(poly)
+            .into_robj()
+            .set_class(["polygon", "Geom"])?;
+
+        res.set_elt(i, poly)?;
     }
-    
-    let polygon = Polygon::new(exterior, linestrings);
 
-    Geom::from(polygon)
-        .into_robj()
-        .set_class(["polygon", "Geom"])
-        .unwrap()
+    dedup.annotate(as_rsgeo_vctr(res, "polygon")?)
 }
 
 /// Create a single `multipolygon` from a list of lists of 2 dimensional matrices.
+/// Panics with the polygon and ring that could not be converted, see
+/// `geom_multipolygon_checked()`.
 pub fn geom_multipolygon(x: List) -> Robj {
-    let res = MultiPolygon::new(
-        x.into_iter()
-            .map(|(_, x)| polygon_inner(List::try_from(x).unwrap()))
-            .collect::<Vec<Polygon>>(),
-    );
+    match geom_multipolygon_checked(x, &GeomOptions::default()) {
+        Ok(mpoly) => mpoly,
+        Err(e) => panic!("{e}"),
+    }
+}
+
+/// Create a single `multipolygon` from a list of lists of 2 dimensional matrices
+/// with OGC ring orientation. See `geom_polygon_oriented()`.
+pub fn geom_multipolygon_oriented(x: List) -> Robj {
+    match geom_multipolygon_checked(x, &GeomOptions { orient: true, ..Default::default() }) {
+        Ok(mpoly) => mpoly,
+        Err(e) => panic!("{e}"),
+    }
+}
+
+/// Like `geom_multipolygon()` but returns an error instead of panicking. Rings are
+/// validated as in `geom_polygon_checked()` and errors name the polygon and ring
+/// that is invalid. See `GeomOptions`.
+pub fn geom_multipolygon_checked(x: List, opts: &GeomOptions) -> Result<Robj> {
+    let mut dedup = Dedup::new(opts.dedup);
 
-    Geom::from(res)
+    let mpoly = Geom::from(MultiPolygon::new(polygons_inner(x, opts, &mut dedup)?))
         .into_robj()
-        .set_class(["multipolygon", "Geom"])
-        .unwrap()
+        .set_class(["multipolygon", "Geom"])?;
+
+    dedup.annotate(mpoly)
+}
+
+/// Create an `rs_MULTIPOLYGON` vector following sf's MULTIPOLYGON nesting: each
+/// element is a list of polygons and each polygon is a list of 2 dimensional
+/// matrices, the first being the exterior ring. `NULL` elements are missing
+/// geometries. Errors with the position of the feature, polygon, and ring that
+/// could not be converted. See `GeomOptions`.
+pub fn geom_multipolygons(x: List, opts: &GeomOptions) -> Result<Robj> {
+    let mut dedup = Dedup::new(opts.dedup);
+    let mut res = List::new(x.len());
+
+    for (i, (_, xi)) in x.into_iter().enumerate() {
+        if xi.is_null() {
+            continue;
+        }
+
+        let polys = List::try_from(xi).map_err(|_| {
+            Error::Other(format!("Feature {} must be a list of polygons", i + 1))
+        })?;
+
+        let polys = polys
+            .into_iter()
+            .enumerate()
+            .map(|(j, (_, poly))| {
+                List::try_from(poly)
+                    .map_err(|_| Error::Other("must be a list of matrices".to_string()))
+                    .and_then(|rings| polygon_inner(rings, opts, &mut dedup))
+                    .map_err(|e| {
+                        Error::Other(format!(
+                            "Failed to create polygon {} of feature {}: {e}",
+                            j + 1,
+                            i + 1
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<Polygon>>>()?;
+
+        let mpoly = Geom::from(MultiPolygon::new(polys))
+            .into_robj()
+            .set_class(["multipolygon", "Geom"])?;
+
+        res.set_elt(i, mpoly)?;
+    }
+
+    dedup.annotate(as_rsgeo_vctr(res, "multipolygon")?)
+}
+
+/// Create a single `polygon` from an extent. `x` is either an sf `bbox` object or a
+/// numeric vector `c(xmin, ymin, xmax, ymax)`. The exterior is the closed 5 point
+/// rectangle in counter-clockwise order. Errors naming the bound if the extent
+/// contains `NA` or is inverted.
+#[extendr]
+pub fn bbox_to_polygon(x: Robj) -> Result<Robj> {
+    let rect = if x.inherits("bbox") {
+        bbox_to_rect(&x).map_err(|e| Error::Other(e.to_string()))?
+    } else {
+        let extent: [f64; 4] = x
+            .as_real_slice()
+            .and_then(|x| x.try_into().ok())
+            .ok_or_else(|| {
+                Error::Other("`x` must be a `bbox` or a numeric vector of length 4".to_string())
+            })?;
+
+        extent_to_rect(extent).map_err(Error::Other)?
+    };
+
+    Geom::from(rect.to_polygon().orient(Direction::Default))
+        .into_robj()
+        .set_class(["polygon", "Geom"])
+}
+
+/// Create a `Rect` from an extent `[xmin, ymin, xmax, ymax]`. Returns an error
+/// naming the bound if any is `NA` or if a minimum is greater than its maximum.
+/// Zero area extents are valid.
+pub fn extent_to_rect(x: [f64; 4]) -> std::result::Result<Rect, String> {
+    let names = ["xmin", "ymin", "xmax", "ymax"];
+
+    if let Some(i) = x.iter().position(|v| v.is_nan()) {
+        return Err(format!("`{}` is `NA`", names[i]));
+    }
+
+    let [xmin, ymin, xmax, ymax] = x;
+
+    if xmin > xmax {
+        return Err(format!("`xmin` ({xmin}) is greater than `xmax` ({xmax})"));
+    }
+
+    if ymin > ymax {
+        return Err(format!("`ymin` ({ymin}) is greater than `ymax` ({ymax})"));
+    }
+
+    Ok(Rect::new(coord! {x: xmin, y: ymin}, coord! {x: xmax, y: ymax}))
+}
+
+/// Create an rsgeo vector from a list of coordinates of mixed types. The type of
+/// each element is inferred from its structure:
+///
+/// - a numeric vector of length 2 is a point
+/// - a matrix is a linestring
+/// - a list of matrices is a polygon
+/// - a list of lists of matrices is a multipolygon
+///
+/// `hints` optionally gives the type of each element to disambiguate a matrix as a
+/// `"multipoint"` or a list of matrices as a `"multilinestring"`. `NA` hints are
+/// inferred. `NULL` elements are missing geometries. The vector's class is
+/// determined from the types of its elements. Errors with the position and a
+/// description of any element that cannot be converted.
+pub fn geoms_from_matrices(x: List, hints: Option<Strings>) -> Result<Robj> {
+    if let Some(hints) = &hints {
+        if hints.len() != x.len() {
+            return Err(Error::Other(format!(
+                "`hints` must be the same length as `x`. Found {} and {}.",
+                hints.len(),
+                x.len()
+            )));
+        }
+    }
+
+    let mut res = List::new(x.len());
+
+    for (i, (_, xi)) in x.into_iter().enumerate() {
+        if xi.is_null() {
+            continue;
+        }
+
+        let hint = hints
+            .as_ref()
+            .map(|hints| hints.elt(i))
+            .filter(|hint| !hint.is_na())
+            .map(|hint| hint.to_lowercase());
+
+        res.set_elt(i, infer_geom(xi, hint.as_deref(), i)?)?;
+    }
+
+    let cls = determine_geoms_class(&res);
+    res.set_class(cls)
+}
+
+// converts a single element of `geoms_from_matrices()`
+fn infer_geom(x: Robj, hint: Option<&str>, i: usize) -> Result<Robj> {
+    let unrecognized = |found: &str| {
+        Error::Other(format!("Cannot create a geometry from element {}: found {found}", i + 1))
+    };
+
+    let invalid = |e: Error| Error::Other(format!("Cannot create a geometry from element {}: {e}", i + 1));
+    let opts = GeomOptions::default();
+
+    if is_coords(&x) {
+        return match hint {
+            None | Some("linestring") => geom_linestring_checked(x, &opts).map_err(invalid),
+            Some("multipoint") => Geom::from(MultiPoint::from(element_coords(x, i, &opts)?))
+                .into_robj()
+                .set_class(["multipoint", "Geom"]),
+            Some(hint) => Err(unrecognized(&format!("a matrix with the hint `{hint}`"))),
+        };
+    }
+
+    let crds = x
+        .as_real_slice()
+        .map(|crds| crds.to_vec())
+        .or_else(|| x.as_integer_slice().map(|crds| crds.iter().map(|v| int_to_f64(*v)).collect()));
+
+    if let Some(crds) = crds {
+        return match (crds.len(), hint) {
+            (2, None | Some("point")) => Ok(geom_point(crds[0], crds[1])),
+            (n, _) => Err(unrecognized(&format!("a numeric vector of length {n}"))),
+        };
+    }
+
+    let parts = match x.as_list() {
+        Some(parts) => parts,
+        None => return Err(unrecognized(&format!("an object of type {:?}", x.rtype()))),
+    };
+
+    let all_matrices = parts.iter().all(|(_, part)| is_coords(&part));
+    let all_lists = parts.iter().all(|(_, part)| part.is_list() && !is_coords(&part));
+
+    for (j, (_, part)) in parts.iter().enumerate() {
+        let valid = if all_matrices {
+            element_coords(part, j, &opts).is_ok()
+        } else {
+            part.as_list()
+                .map(|rings| {
+                    rings.len() > 0
+                        && rings
+                            .iter()
+                            .enumerate()
+                            .all(|(k, (_, ring))| element_coords(ring, k, &opts).is_ok())
+                })
+                .unwrap_or(false)
+        };
+
+        if !valid {
+            return Err(unrecognized(&format!("a list with an invalid component {}", j + 1)));
+        }
+    }
+
+    match hint {
+        _ if parts.len() == 0 => Err(unrecognized("an empty list")),
+        None | Some("polygon") if all_matrices => geom_polygon_checked(parts, &opts).map_err(invalid),
+        Some("multilinestring") if all_matrices => geom_multilinestring_checked(parts, &opts).map_err(invalid),
+        None | Some("multipolygon") if all_lists => geom_multipolygon_checked(parts, &opts).map_err(invalid),
+        Some(hint) => Err(unrecognized(&format!("a list with the hint `{hint}`"))),
+        None => Err(unrecognized("a list mixing matrices and lists")),
+    }
 }
 
 // First, I need to take a matrix and convert into coordinates
-/// Convert an `RMatrix<f64>` into a vector of `Coords`.
-pub fn matrix_to_coords(x: RMatrix<f64>) -> Vec<Coord> {
-    let nrow = x.nrows();
-    let ncol = x.ncols();
+/// Convert a double or integer matrix into a vector of `Coords`. Integers are
+/// converted to `f64` as they are read and `NA` integers become `NA` doubles.
+/// Panics if the matrix does not have exactly 2 columns. Non-finite coordinates
+/// are kept as is, use `coords_from_robj()` with a `NaPolicy` to reject or skip
+/// them.
+pub fn matrix_to_coords(x: impl Into<Robj>) -> Vec<Coord> {
+    let opts = GeomOptions { na_policy: NaPolicy::Keep, ..Default::default() };
 
-    if ncol != 2 {
-        panic!("Matrix should have only 2 columns for x and y coordinates, respectively.")
+    match robj_coords(&x.into(), "Matrix", &opts) {
+        Ok(coords) => coords,
+        Err(e) => panic!("{e}"),
     }
+}
+
+/// How non-finite coordinates, `NA`, `NaN`, and `Inf`, are handled when reading
+/// coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NaPolicy {
+    /// Return an error with the row and column of the first non-finite value.
+    #[default]
+    Error,
+    /// Drop rows with a non-finite value.
+    Skip,
+    /// Keep non-finite values as is. `NA` is stored as a `NaN`.
+    Keep,
+}
 
-    //let n = nrow.clone();
-    let mut coords: Vec<Coord> = Vec::with_capacity(nrow);
+/// Apply a `NaPolicy` to coordinates. Error messages give the 1-based row and the
+/// column, e.g. `"row 42, y is NA"`, distinguishing `NA`, `NaN`, and `Inf`.
+pub fn apply_na_policy(x: Vec<Coord>, policy: NaPolicy) -> Result<Vec<Coord>> {
+    let is_finite = |crd: &Coord| crd.x.is_finite() && crd.y.is_finite();
 
-    for i in 0..nrow {
-        let crd = coord! {x: x[[i, 0]], y: x[[i, 1]]};
-        coords.push(crd);
+    match policy {
+        NaPolicy::Keep => Ok(x),
+        NaPolicy::Skip => Ok(x.into_iter().filter(is_finite).collect()),
+        NaPolicy::Error => match x.iter().position(|crd| !is_finite(crd)) {
+            None => Ok(x),
+            Some(i) => {
+                let (col, value) = if x[i].x.is_finite() { ("y", x[i].y) } else { ("x", x[i].x) };
+                Err(Error::Other(format!("row {}, {col} is {}", i + 1, describe_non_finite(value))))
+            }
+        },
     }
+}
+
+// R's NA_real_ is a NaN with the payload 1954
+fn describe_non_finite(x: f64) -> &'static str {
+    if x.is_nan() && (x.to_bits() & 0xFFFF_FFFF) == 1954 {
+        "NA"
+    } else if x.is_nan() {
+        "NaN"
+    } else if x > 0.0 {
+        "Inf"
+    } else {
+        "-Inf"
+    }
+}
+
+/// Convert an `RMatrix<f64>` into a vector of `Coords` without checking its
+/// dimensions. The matrix is assumed to have at least 2 columns and the first 2
+/// are read by position. For callers that have already validated their input;
+/// use `matrix_to_coords()` otherwise.
+pub fn matrix_to_coords_unchecked(x: RMatrix<f64>) -> Vec<Coord> {
+    slice_to_coords(&x.data()[..x.nrows() * 2])
+}
+
+/// Convert a double or integer matrix into a vector of `Coords` without rejecting
+/// missing values and report which rows have an `NA` or `NaN` in either column.
+/// The rows are 1-based, as in the errors of `apply_na_policy()`. The coordinates
+/// are returned as is so that the caller can decide whether to drop, interpolate,
+/// or error on the reported rows. Errors if the matrix does not have exactly 2
+/// columns.
+pub fn matrix_to_coords_report(x: impl Into<Robj>) -> Result<(Vec<Coord>, Vec<usize>)> {
+    let opts = GeomOptions { na_policy: NaPolicy::Keep, ..Default::default() };
+    let coords = robj_coords(&x.into(), "Matrix", &opts)?;
+
+    let na_rows = coords
+        .iter()
+        .enumerate()
+        .filter(|(_, crd)| crd.x.is_nan() || crd.y.is_nan())
+        .map(|(i, _)| i + 1)
+        .collect::<Vec<usize>>();
+
+    Ok((coords, na_rows))
+}
+
+// Convert the column-major data of an n x 2 matrix into a vector of `Coords`.
+// The x and y columns are read directly as contiguous slices rather than
+// indexing each cell of the matrix.
+fn slice_to_coords(x: &[f64]) -> Vec<Coord> {
+    let (xs, ys) = x.split_at(x.len() / 2);
+
+    let mut coords: Vec<Coord> = Vec::with_capacity(xs.len());
+    coords.extend(
+        xs.iter()
+            .zip(ys.iter())
+            .map(|(&x, &y)| coord! {x: x, y: y})
+    );
     coords
 }
 
+// Convert the column-major data of an n x 2 integer matrix into a vector of
+// `Coords`. Values are converted to `f64` as they are read.
+fn int_slice_to_coords(x: &[i32]) -> Vec<Coord> {
+    let (xs, ys) = x.split_at(x.len() / 2);
+
+    xs.iter()
+        .zip(ys.iter())
+        .map(|(&x, &y)| coord! {x: int_to_f64(x), y: int_to_f64(y)})
+        .collect()
+}
+
+// NA_integer_ is i32::MIN
+fn int_to_f64(x: i32) -> f64 {
+    if x == i32::MIN {
+        Rfloat::na().inner()
+    } else {
+        x as f64
+    }
+}
+
+/// Convert a double or integer matrix into a vector of `Points`. Is used
+/// internally to create `MultiPoint`s. Panics like `matrix_to_coords()`.
+pub fn matrix_to_points(x: impl Into<Robj>) -> Vec<Point> {
+    matrix_to_coords(x).into_iter().map(Point::from).collect()
+}
 
-/// Convert an `RMatrix<f64>` into a vector of `Points`. Is
-/// used internally to create `MultiPoint`s.
-pub fn matrix_to_points(x: RMatrix<f64>) -> Vec<Point> {
-    let nrow = x.nrows();
-    let ncol = x.ncols();
+/// Convert a numeric matrix or a data.frame with 2 columns into a vector of
+/// `Coords`. Matrices are read directly from their column slices. The columns of
+/// a data.frame are read as the x and y coordinates without converting it to a
+/// matrix and a data.frame with zero rows is empty like a zero row matrix.
+/// Returns an error describing the problem otherwise. Non-finite
+/// coordinates are handled according to `opts.na_policy` and duplicates are
+/// removed with `opts.dedup`.
+pub fn coords_from_robj(x: &Robj, opts: &GeomOptions) -> Result<Vec<Coord>> {
+    let coords = robj_coords(x, "`x`", opts)?;
+    Ok(Dedup::new(opts.dedup).apply(coords))
+}
 
-    if ncol != 2 {
-        panic!("Matrix should have only 2 columns for x and y coordinates, respectively.")
+// is `x` a coordinate matrix or data.frame
+fn is_coords(x: &Robj) -> bool {
+    x.is_matrix() || x.inherits("data.frame")
+}
+
+// the `i`th element of a list as coordinates
+fn element_coords(x: Robj, i: usize, opts: &GeomOptions) -> Result<Vec<Coord>> {
+    robj_coords(&x, &format!("Element {}", i + 1), opts)
+}
+
+// `what` describes `x` in error messages
+fn robj_coords(x: &Robj, what: &str, opts: &GeomOptions) -> Result<Vec<Coord>> {
+    let coords = robj_coords_cols(x, what, opts.xy_only)?;
+    apply_na_policy(coords, opts.na_policy).map_err(|e| Error::Other(format!("{what}: {e}")))
+}
+
+// if `xy_only` is true the first 2 columns of a wider matrix are used
+fn robj_coords_cols(x: &Robj, what: &str, xy_only: bool) -> Result<Vec<Coord>> {
+    if x.inherits("data.frame") {
+        return dataframe_coords(x, what);
     }
 
-    //let n = nrow.clone();
-    let mut coords: Vec<Point> = Vec::with_capacity(nrow);
+    let check_ncol = |ncol: usize| {
+        if ncol == 2 || (xy_only && ncol > 2) {
+            Ok(())
+        } else if xy_only {
+            Err(Error::Other(format!("{what} must have at least 2 columns for x and y coordinates. Found {ncol}.")))
+        } else {
+            Err(Error::Other(format!("{what} must have 2 columns for x and y coordinates. Found {ncol}.")))
+        }
+    };
 
-    for i in 0..nrow {
-        let crd = point! {x: x[[i, 0]], y: x[[i, 1]]};
-        coords.push(crd);
+    // `matrix(nrow = 0, ncol = 2)` is a logical matrix. It has no coordinates so
+    // it is accepted as an empty geometry or ring.
+    if x.is_logical() && x.len() == 0 {
+        if let Some(dim) = x.dim() {
+            if dim.len() == 2 {
+                check_ncol(dim[1].inner() as usize)?;
+                return Ok(Vec::new());
+            }
+        }
     }
-    coords
+
+    // the double path is tried first so it is unaffected by integer support
+    if let Ok(mat) = RMatrix::<f64>::try_from(x.clone()) {
+        check_ncol(mat.ncols())?;
+
+        return Ok(match named_xy_columns(x) {
+            Some(cols) => {
+                let (xs, ys) = column_pair(mat.data(), mat.nrows(), cols);
+                xs.iter().zip(ys.iter()).map(|(&x, &y)| coord! {x: x, y: y}).collect()
+            }
+            None => matrix_to_coords_unchecked(mat),
+        });
+    }
+
+    if let Ok(mat) = RMatrix::<i32>::try_from(x.clone()) {
+        check_ncol(mat.ncols())?;
+
+        return Ok(match named_xy_columns(x) {
+            Some(cols) => {
+                let (xs, ys) = column_pair(mat.data(), mat.nrows(), cols);
+                xs.iter()
+                    .zip(ys.iter())
+                    .map(|(&x, &y)| coord! {x: int_to_f64(x), y: int_to_f64(y)})
+                    .collect()
+            }
+            None => int_slice_to_coords(&mat.data()[..mat.nrows() * 2]),
+        });
+    }
+
+    Err(Error::Other(format!("{what} must be a numeric matrix or data.frame")))
 }
 
-// utility function to take a list and convert to a Polygon
-// will be used to collect into `Vec<Polygon>` and thus into `MultiPolygon`
-fn polygon_inner(x: List) -> Polygon {
+// the positions of the columns named "x" and "y", ignoring case, if a matrix has
+// column names such as those written by sfheaders. Matrices without both names
+// are read by position.
+fn named_xy_columns(x: &Robj) -> Option<(usize, usize)> {
+    let dimnames = x.get_attrib("dimnames")?.as_list()?;
+    let colnames = dimnames.elt(1).ok()?.as_str_vector()?;
+
+    let find = |name: &str| colnames.iter().position(|col| col.eq_ignore_ascii_case(name));
+    let cols = (find("x")?, find("y")?);
+
+    // nothing to do if the columns are already in order
+    if cols == (0, 1) {
+        None
+    } else {
+        Some(cols)
+    }
+}
+
+// the columns `cols` of column-major matrix data with `nrow` rows
+fn column_pair<T>(x: &[T], nrow: usize, cols: (usize, usize)) -> (&[T], &[T]) {
+    let (ix, iy) = cols;
+    (&x[ix * nrow..(ix + 1) * nrow], &x[iy * nrow..(iy + 1) * nrow])
+}
+
+fn dataframe_coords(x: &Robj, what: &str) -> Result<Vec<Coord>> {
+    let cols = x
+        .as_list()
+        .ok_or_else(|| Error::Other(format!("{what} must be a data.frame")))?;
+
+    if cols.len() != 2 {
+        return Err(Error::Other(format!(
+            "{what} must have exactly 2 columns for x and y coordinates. Found {}.",
+            cols.len()
+        )));
+    }
+
+    let column = |j: usize| -> Result<Vec<f64>> {
+        let (name, col) = cols.iter().nth(j).unwrap();
+
+        if let Some(values) = col.as_real_slice() {
+            return Ok(values.to_vec());
+        }
+
+        if let Some(values) = col.as_integer_slice() {
+            return Ok(values.iter().map(|v| int_to_f64(*v)).collect());
+        }
+
+        Err(Error::Other(format!(
+            "Column `{name}` of {what} must be numeric. Found {:?}.",
+            col.rtype()
+        )))
+    };
+
+    let (xs, ys) = (column(0)?, column(1)?);
+
+    // zero rows are empty, as for a zero row matrix
+    Ok(xs.into_iter().zip(ys).map(|(x, y)| coord! {x: x, y: y}).collect())
+}
+
+/// Convert the coordinates of a MULTIPOINT into a vector of `Points`. sf stores a
+/// MULTIPOINT as an n x 2 matrix but some writers produce a list of points where
+/// each element is a numeric vector of length 2 or a POINT sfg. Both forms, and
+/// a data.frame, are supported. Returns an error if `x` is neither. Non-finite
+/// coordinates are handled according to `opts.na_policy`.
+pub fn multipoint_to_points(x: &Robj, opts: &GeomOptions) -> Result<Vec<Point>> {
+    if is_coords(x) {
+        let coords = robj_coords(x, "MULTIPOINT", opts)?;
+        return Ok(coords.into_iter().map(Point::from).collect());
+    }
+
+    let pnts = x.as_list().ok_or_else(|| {
+        Error::Other("MULTIPOINT must be a matrix or a list of points".to_string())
+    })?;
+
+    let coords = pnts
+        .iter()
+        .enumerate()
+        .map(|(i, (_, pnt))| match pnt.as_real_slice() {
+            Some(crds) if crds.len() >= 2 => Ok(coord! {x: crds[0], y: crds[1]}),
+            _ => Err(Error::Other(format!(
+                "Point {} of MULTIPOINT must be a numeric vector of length 2",
+                i + 1
+            ))),
+        })
+        .collect::<Result<Vec<Coord>>>()?;
+
+    let coords = apply_na_policy(coords, opts.na_policy)
+        .map_err(|e| Error::Other(format!("MULTIPOINT: {e}")))?;
+
+    Ok(coords.into_iter().map(Point::from).collect())
+}
+
+/// Remove consecutive duplicate coordinates. Only exact duplicates are removed and
+/// the first and last coordinates of a closed ring are kept as they are not
+/// consecutive. Returns the coordinates and the number removed.
+pub fn dedup_coords(mut x: Vec<Coord>) -> (Vec<Coord>, usize) {
     let n = x.len();
-    let mut linestrings: Vec<LineString> = Vec::with_capacity(n);
+    x.dedup();
+    let dropped = n - x.len();
+    (x, dropped)
+}
+
+// optionally removes consecutive duplicate coordinates and counts them across
+// all of the coordinates read by a constructor
+struct Dedup {
+    enabled: bool,
+    dropped: usize,
+}
+
+impl Dedup {
+    fn new(enabled: bool) -> Self {
+        Self { enabled, dropped: 0 }
+    }
+
+    fn apply(&mut self, x: Vec<Coord>) -> Vec<Coord> {
+        if !self.enabled {
+            return x;
+        }
 
-    let exterior = matrix_to_coords(x[0].as_matrix().unwrap());
-    let exterior = LineString::new(exterior);
+        let (x, dropped) = dedup_coords(x);
+        self.dropped += dropped;
+        x
+    }
 
-    if n > 1 {
-        for i in 1..n {
-            let xi: RMatrix<f64> = x[i].to_owned().try_into().unwrap();
-            let coords = matrix_to_coords(xi);
-            let line = LineString::new(coords);
-            linestrings.push(line);
+    // records the number of dropped coordinates so callers can warn
+    fn annotate(&self, x: Robj) -> Result<Robj> {
+        if self.enabled {
+            x.set_attrib("n_dropped", self.dropped as i32)
+        } else {
+            Ok(x)
         }
     }
+}
+
+// a linestring must have 0 or at least 2 coordinates. A single coordinate is
+// only accepted if `allow_degenerate` is true
+fn linestring_inner(coords: Vec<Coord>, what: &str, allow_degenerate: bool) -> Result<LineString> {
+    if coords.len() == 1 && !allow_degenerate {
+        return Err(Error::Other(format!(
+            "{what} must have at least 2 coordinates for a linestring. Found 1."
+        )));
+    }
+
+    Ok(LineString::new(coords))
+}
+
+fn polygons_inner(x: List, opts: &GeomOptions, dedup: &mut Dedup) -> Result<Vec<Polygon>> {
+    x.into_iter()
+        .enumerate()
+        .map(|(i, (_, poly))| {
+            List::try_from(poly)
+                .map_err(|_| Error::Other("must be a list of matrices".to_string()))
+                .and_then(|rings| polygon_inner(rings, opts, dedup))
+                .map_err(|e| Error::Other(format!("Polygon {}: {e}", i + 1)))
+        })
+        .collect()
+}
+
+// validates the `i`th ring of a polygon and closes it if needed
+// zero row rings are an error as they would be written as a malformed sf POLYGON
+fn check_ring(coords: Vec<Coord>, i: usize, auto_close: bool) -> Result<Vec<Coord>> {
+    let ring = if i == 0 {
+        "Ring 1 (exterior)".to_string()
+    } else {
+        format!("Ring {} (interior {i})", i + 1)
+    };
+
+    if coords.is_empty() {
+        return Err(Error::Other(format!("{ring} has no coordinates")));
+    }
+
+    let mut distinct: Vec<Coord> = Vec::with_capacity(3);
+    for crd in coords.iter() {
+        if !distinct.contains(crd) {
+            distinct.push(*crd);
+            if distinct.len() == 3 {
+                break;
+            }
+        }
+    }
+
+    if distinct.len() < 3 {
+        return Err(Error::Other(format!(
+            "{ring} must have at least 3 distinct coordinates. Found {}.",
+            distinct.len()
+        )));
+    }
+
+    if coords.first() != coords.last() && !auto_close {
+        return Err(Error::Other(format!(
+            "{ring} is not closed: the first and last coordinates differ"
+        )));
+    }
+
+    Ok(close_ring(coords))
+}
+
+/// Convert a list of 2 dimensional matrices into a `Polygon`, as in an sf POLYGON.
+/// The first matrix is the exterior ring and any others are interior rings, or
+/// holes, in the order given. Rings are closed if needed and must have at least 3
+/// distinct coordinates. Ring orientation is kept as provided. Errors name the
+/// ring that could not be converted.
+///
+/// ```
+/// use extendr_api::prelude::*;
+/// use sfconversions::constructors::list_to_polygon;
+///
+/// let exterior = RMatrix::new_matrix(4, 2, |r, c| [[0.0, 10.0, 10.0, 0.0], [0.0, 0.0, 10.0, 10.0]][c][r]);
+/// let hole = RMatrix::new_matrix(3, 2, |r, c| [[2.0, 4.0, 2.0], [2.0, 2.0, 4.0]][c][r]);
+///
+/// let poly = list_to_polygon(list!(exterior, hole)).unwrap();
+/// assert_eq!(poly.interiors().len(), 1);
+/// ```
+pub fn list_to_polygon(x: List) -> Result<Polygon> {
+    polygon_inner(x, &GeomOptions::default(), &mut Dedup::new(false))
+}
+
+// used by `fromsf` to read POLYGON and MULTIPOLYGON sfg with `GeomOptions::lenient()`
+pub(crate) fn list_to_polygon_with(x: List, opts: &GeomOptions) -> Result<Polygon> {
+    polygon_inner(x, opts, &mut Dedup::new(opts.dedup))
+}
+
+pub(crate) fn list_to_polygons_with(x: List, opts: &GeomOptions) -> Result<Vec<Polygon>> {
+    polygons_inner(x, opts, &mut Dedup::new(opts.dedup))
+}
+
+/// Convert a list of polygons, each a list of 2 dimensional matrices as in
+/// `list_to_polygon()`, into a `Vec<Polygon>` following sf's MULTIPOLYGON nesting.
+/// Errors name the polygon and ring that could not be converted.
+pub fn list_to_polygons(x: List) -> Result<Vec<Polygon>> {
+    polygons_inner(x, &GeomOptions::default(), &mut Dedup::new(false))
+}
+
+// utility function to take a list and convert to a Polygon
+// will be used to collect into `Vec<Polygon>` and thus into `MultiPolygon`
+// duplicates are removed before the rings are validated
+fn polygon_inner(x: List, opts: &GeomOptions, dedup: &mut Dedup) -> Result<Polygon> {
+    if x.len() == 0 {
+        return if opts.validate_rings {
+            Err(Error::Other("A polygon must have at least 1 ring".to_string()))
+        } else {
+            Ok(Polygon::new(LineString::new(vec![]), vec![]))
+        };
+    }
+
+    let mut rings = x
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, ring))| {
+            let coords = dedup.apply(robj_coords(&ring, &format!("Ring {}", i + 1), opts)?);
+
+            if opts.validate_rings {
+                check_ring(coords, i, opts.auto_close).map(LineString::new)
+            } else {
+                Ok(LineString::new(coords))
+            }
+        })
+        .collect::<Result<Vec<LineString>>>()?;
 
-    Polygon::new(exterior, linestrings)
+    let exterior = rings.remove(0);
+    let polygon = Polygon::new(exterior, rings);
+
+    if opts.orient {
+        Ok(polygon.orient(Direction::Default))
+    } else {
+        Ok(polygon)
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::Geometry;
+
+    fn geometry(x: &Robj) -> Geometry {
+        <&Geom>::from_robj(x).unwrap().geom.clone()
+    }
+
+    fn linestring(x: &Robj) -> LineString {
+        geometry(x).try_into().unwrap()
+    }
+
+    #[test]
+    fn linestring_needs_two_coordinates() {
+        test! {
+            let opts = GeomOptions::default();
+
+            let empty = geom_linestring_checked(R!("matrix(numeric(0), ncol = 2)").unwrap(), &opts).unwrap();
+            assert_eq!(linestring(&empty).0.len(), 0);
+
+            let err = geom_linestring_checked(R!("matrix(c(1, 2), ncol = 2)").unwrap(), &opts).unwrap_err();
+            assert!(err.to_string().contains("Found 1"));
+
+            let two = geom_linestring_checked(R!("matrix(c(0, 1, 0, 1), ncol = 2)").unwrap(), &opts).unwrap();
+            assert_eq!(linestring(&two), LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]));
+        }
+    }
+
+    #[test]
+    fn allow_degenerate_linestring() {
+        test! {
+            let opts = GeomOptions { allow_degenerate: true, ..Default::default() };
+            let one = geom_linestring_checked(R!("matrix(c(1, 2), ncol = 2)").unwrap(), &opts).unwrap();
+            assert_eq!(linestring(&one), LineString::from(vec![(1.0, 2.0)]));
+        }
+    }
+
+    #[test]
+    fn linestrings_report_the_element() {
+        test! {
+            let x = List::try_from(R!("list(NULL, matrix(c(0, 1, 0, 1), ncol = 2), matrix(c(1, 2), ncol = 2))").unwrap()).unwrap();
+            let err = geom_linestrings(x, &GeomOptions::default()).unwrap_err();
+            assert!(err.to_string().contains("Element 3"));
+        }
+    }
+
+    #[test]
+    fn orient_polygon_rings() {
+        use geo::Area;
+
+        test! {
+            // a clockwise exterior and a counter-clockwise hole
+            let x = List::try_from(R!("list(list(
+                matrix(c(0, 0, 10, 10, 0, 0, 10, 10, 0, 0), ncol = 2),
+                matrix(c(2, 4, 4, 2, 2, 2, 2, 4, 4, 2), ncol = 2)
+            ))").unwrap()).unwrap();
+
+            let polygon = |x: &Robj, i: usize| -> Polygon {
+                geometry(&x.as_list().unwrap().elt(i).unwrap()).try_into().unwrap()
+            };
+
+            let signed_area = |ring: &LineString| Polygon::new(ring.clone(), vec![]).signed_area();
+
+            let kept = geom_polygons(x.clone(), &GeomOptions::default()).unwrap();
+            let kept = polygon(&kept, 0);
+            assert!(signed_area(kept.exterior()) < 0.0);
+            assert!(signed_area(&kept.interiors()[0]) > 0.0);
+
+            let opts = GeomOptions { orient: true, ..Default::default() };
+            let oriented = geom_polygons(x, &opts).unwrap();
+            let oriented = polygon(&oriented, 0);
+            assert!(signed_area(oriented.exterior()) > 0.0);
+            assert!(signed_area(&oriented.interiors()[0]) < 0.0);
+            assert_eq!(oriented.unsigned_area(), kept.unsigned_area());
+        }
+    }
+
+    #[test]
+    fn dedup_consecutive_coordinates() {
+        let crds = |x: &[(f64, f64)]| x.iter().map(|&(x, y)| coord! {x: x, y: y}).collect::<Vec<Coord>>();
+
+        let (res, dropped) = dedup_coords(crds(&[(0.0, 0.0), (1.0, 1.0), (1.0, 1.0), (1.0, 1.0), (2.0, 0.0)]));
+        assert_eq!(res, crds(&[(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)]));
+        assert_eq!(dropped, 2);
+
+        // the closing coordinate of a ring is not consecutive with the first
+        let ring = crds(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)]);
+        assert_eq!(dedup_coords(ring.clone()), (ring, 0));
+    }
+
+    #[test]
+    fn dedup_in_constructors() {
+        test! {
+            let opts = GeomOptions { dedup: true, ..Default::default() };
+
+            let lns = geom_linestring_checked(R!("matrix(c(0, 1, 1, 1, 2, 0, 1, 1, 1, 0), ncol = 2)").unwrap(), &opts).unwrap();
+            assert_eq!(linestring(&lns), LineString::from(vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)]));
+            assert_eq!(lns.get_attrib("n_dropped").unwrap().as_integer(), Some(2));
+
+            let x = List::try_from(R!("list(matrix(c(0, 1, 1, 0, 1, 1), ncol = 2), matrix(c(5, 6, 5, 6), ncol = 2))").unwrap()).unwrap();
+            let mlns = geom_multilinestring_checked(x, &opts).unwrap();
+            assert_eq!(mlns.get_attrib("n_dropped").unwrap().as_integer(), Some(1));
+
+            let x = List::try_from(R!("list(list(list(matrix(c(0, 1, 1, 1, 0, 0, 0, 0, 1, 0), ncol = 2))))").unwrap()).unwrap();
+            let mpoly = geom_multipolygons(x, &opts).unwrap();
+            assert_eq!(mpoly.get_attrib("n_dropped").unwrap().as_integer(), Some(1));
+
+            let mpoly: MultiPolygon = geometry(&mpoly.as_list().unwrap().elt(0).unwrap()).try_into().unwrap();
+            assert_eq!(mpoly.0[0].exterior().0.len(), 4);
+        }
+    }
+
+    #[test]
+    fn na_policy_describes_the_value() {
+        let na = Rfloat::na().inner();
+        let with = |x: f64| vec![coord! {x: 0.0, y: 0.0}, coord! {x: 1.0, y: x}];
+
+        let err = |x: Vec<Coord>| apply_na_policy(x, NaPolicy::Error).unwrap_err().to_string();
+        assert_eq!(err(with(na)), "row 2, y is NA");
+        assert_eq!(err(with(f64::NAN)), "row 2, y is NaN");
+        assert_eq!(err(with(f64::INFINITY)), "row 2, y is Inf");
+        assert_eq!(err(with(f64::NEG_INFINITY)), "row 2, y is -Inf");
+        assert_eq!(err(vec![coord! {x: na, y: f64::NAN}]), "row 1, x is NA");
+
+        for value in [na, f64::NAN, f64::INFINITY] {
+            let skipped = apply_na_policy(with(value), NaPolicy::Skip).unwrap();
+            assert_eq!(skipped, vec![coord! {x: 0.0, y: 0.0}]);
+
+            let kept = apply_na_policy(with(value), NaPolicy::Keep).unwrap();
+            assert_eq!(kept.len(), 2);
+            assert_eq!(kept[1].y.to_bits(), value.to_bits());
+        }
+    }
+
+    #[test]
+    fn na_policy_in_constructors() {
+        test! {
+            let x = R!("list(matrix(c(0, 1, 2, 0, 1, 2), ncol = 2), matrix(c(0, 1, 2, 0, NA, 2), ncol = 2))").unwrap();
+
+            let err = geom_linestrings(List::try_from(x.clone()).unwrap(), &GeomOptions::default()).unwrap_err();
+            assert_eq!(err.to_string(), "Element 2: row 2, y is NA");
+
+            let opts = GeomOptions { na_policy: NaPolicy::Skip, ..Default::default() };
+            let lns = geom_linestrings(List::try_from(x).unwrap(), &opts).unwrap();
+            let lns = linestring(&lns.as_list().unwrap().elt(1).unwrap());
+            assert_eq!(lns, LineString::from(vec![(0.0, 0.0), (2.0, 2.0)]));
+
+            let opts = GeomOptions { na_policy: NaPolicy::Keep, ..Default::default() };
+            let pnts = multipoint_to_points(&R!("matrix(c(0, NaN, 0, 1), ncol = 2)").unwrap(), &opts).unwrap();
+            assert!(pnts[1].x().is_nan());
+        }
+    }
+
+    #[test]
+    fn wide_matrices_need_xy_only() {
+        test! {
+            let three = R!("matrix(c(0, 1, 0, 1, 5, 6), ncol = 3)").unwrap();
+            let five = R!("matrix(c(0, 1, 0, 1, 5, 6, 7, 8, 9, 10), ncol = 5)").unwrap();
+
+            for (x, ncol) in [(&three, 3), (&five, 5)] {
+                let err = geom_linestring_checked(x.clone(), &GeomOptions::default()).unwrap_err();
+                assert!(err.to_string().contains(&format!("Found {ncol}.")));
+
+                let opts = GeomOptions { xy_only: true, ..Default::default() };
+                let lns = geom_linestring_checked(x.clone(), &opts).unwrap();
+                assert_eq!(linestring(&lns), LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]));
+            }
+        }
+    }
+
+    #[test]
+    fn integer_and_double_matrices_are_identical() {
+        test! {
+            let int = R!("matrix(1:10, ncol = 2)").unwrap();
+            let dbl = R!("matrix(as.numeric(1:10), ncol = 2)").unwrap();
+            assert_eq!(matrix_to_coords(int.clone()), matrix_to_coords(dbl.clone()));
+
+            let opts = GeomOptions::default();
+            let int = geom_linestring_checked(int, &opts).unwrap();
+            let dbl = geom_linestring_checked(dbl, &opts).unwrap();
+            assert_eq!(linestring(&int), linestring(&dbl));
+
+            let coords = matrix_to_coords(R!("matrix(c(1L, NA, 3L, 4L), ncol = 2)").unwrap());
+            assert_eq!(describe_non_finite(coords[1].x), "NA");
+        }
+    }
+
+    #[test]
+    fn check_ring_validates_and_closes() {
+        let crds = |x: &[(f64, f64)]| x.iter().map(|&(x, y)| coord! {x: x, y: y}).collect::<Vec<Coord>>();
+
+        let open = crds(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]);
+        let closed = crds(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)]);
+
+        assert_eq!(check_ring(open.clone(), 0, true).unwrap(), closed);
+        assert_eq!(check_ring(closed.clone(), 0, false).unwrap(), closed);
+
+        let err = check_ring(open, 0, false).unwrap_err();
+        assert_eq!(err.to_string(), "Ring 1 (exterior) is not closed: the first and last coordinates differ");
+
+        let err = check_ring(crds(&[(0.0, 0.0), (1.0, 1.0)]), 2, true).unwrap_err();
+        assert_eq!(err.to_string(), "Ring 3 (interior 2) must have at least 3 distinct coordinates. Found 2.");
+
+        let err = check_ring(vec![], 0, true).unwrap_err();
+        assert_eq!(err.to_string(), "Ring 1 (exterior) has no coordinates");
+    }
+
+    #[test]
+    fn polygon_rings_are_validated() {
+        test! {
+            let exterior = "matrix(c(0, 10, 10, 0, 0, 0, 10, 10), ncol = 2)";
+            let closed = "matrix(c(0, 10, 10, 0, 0, 0, 0, 10, 10, 0), ncol = 2)";
+            let hole = "matrix(c(2, 4, 2, 2), ncol = 2)";
+
+            let rings = |x: &str| List::try_from(eval_string(&format!("list({x})")).unwrap()).unwrap();
+            let polygon = |x: &Robj| -> Polygon { geometry(x).try_into().unwrap() };
+
+            // an unclosed exterior is closed by default
+            let poly = geom_polygon_checked(rings(exterior), &GeomOptions::default()).unwrap();
+            assert_eq!(polygon(&poly).exterior().0.len(), 5);
+            assert!(polygon(&poly).exterior().is_closed());
+
+            let strict = GeomOptions { auto_close: false, ..Default::default() };
+            let err = geom_polygon_checked(rings(exterior), &strict).unwrap_err();
+            assert!(err.to_string().contains("Ring 1 (exterior) is not closed"));
+
+            let poly = geom_polygon_checked(rings(closed), &strict).unwrap();
+            assert_eq!(polygon(&poly).exterior().0.len(), 5);
+
+            let with_hole = format!("{closed}, {hole}");
+            let err = geom_polygon_checked(rings(&with_hole), &GeomOptions::default()).unwrap_err();
+            assert!(err.to_string().contains("Ring 2 (interior 1) must have at least 3 distinct coordinates"));
+
+            // rings are not validated when reading sf
+            let poly = list_to_polygon_with(rings(&with_hole), &GeomOptions::lenient()).unwrap();
+            assert_eq!(poly.interiors().len(), 1);
+
+            let empty = list_to_polygon_with(List::new(0), &GeomOptions::lenient()).unwrap();
+            assert!(empty.exterior().0.is_empty());
+        }
+    }
+
+    #[test]
+    fn errors_name_every_nesting_level() {
+        test! {
+            let opts = GeomOptions::default();
+            let bad = "matrix(1:6, ncol = 3)";
+            let ring = "matrix(c(0, 1, 1, 0, 0, 0, 1, 0), ncol = 2)";
+
+            let list = |x: String| List::try_from(eval_string(&x).unwrap()).unwrap();
+
+            let err = geom_multilinestring_checked(list(format!("list({ring}, {bad})")), &opts).unwrap_err();
+            assert_eq!(err.to_string(), "Component 2 must have 2 columns for x and y coordinates. Found 3.");
+
+            let err = geom_polygon_checked(list(format!("list({ring}, {bad})")), &opts).unwrap_err();
+            assert_eq!(err.to_string(), "Ring 2 must have 2 columns for x and y coordinates. Found 3.");
+
+            let mpoly = format!("list(list({ring}), list({ring}), list({ring}, {bad}))");
+            let err = geom_multipolygon_checked(list(mpoly.clone()), &opts).unwrap_err();
+            assert_eq!(err.to_string(), "Polygon 3: Ring 2 must have 2 columns for x and y coordinates. Found 3.");
+
+            let err = geom_polygons(list(format!("list(NULL, list({ring}, {bad}))")), &opts).unwrap_err();
+            assert!(err.to_string().starts_with("Failed to create feature 2: Ring 2 "));
+
+            let err = geom_multipolygons(list(format!("list({mpoly})")), &opts).unwrap_err();
+            assert!(err.to_string().starts_with("Failed to create polygon 3 of feature 1: Ring 2 "));
+
+            let err = geom_multilinestrings(list(format!("list(list({ring}), list({ring}, {bad}))")), &opts).unwrap_err();
+            assert!(err.to_string().starts_with("Component 2 of feature 2 "));
+        }
+    }
+
+    #[test]
+    fn points_from_xy() {
+        test! {
+            let res = geom_points(Doubles::from_values([1.0, 2.0]), Doubles::from_values([3.0, Rfloat::na().inner()])).unwrap();
+            assert!(res.inherits("rs_POINT"));
+
+            let res = List::try_from(res).unwrap();
+            assert_eq!(geometry(&res.elt(0).unwrap()), Point::new(1.0, 3.0).into());
+            assert!(matches!(geometry(&res.elt(1).unwrap()), Geometry::Point(pnt) if pnt.x().is_nan()));
+
+            let empty = geom_points(Doubles::new(0), Doubles::new(0)).unwrap();
+            assert!(empty.inherits("rs_POINT"));
+            assert_eq!(empty.len(), 0);
+
+            assert!(geom_points(Doubles::new(1), Doubles::new(0)).is_err());
+        }
+    }
+
+    #[test]
+    fn geoms_from_matrices_errors_instead_of_panicking() {
+        test! {
+            let x = List::try_from(R!("list(
+                c(1, 2),
+                list(matrix(c(0, 1, 1, 0, 0, 0, 1, 0), ncol = 2)),
+                list(matrix(c(0, 1, 0, 1), ncol = 2))
+            )").unwrap()).unwrap();
+
+            let err = geoms_from_matrices(x, None).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Cannot create a geometry from element 3: Ring 1 (exterior) must have at least 3 distinct coordinates. Found 2."
+            );
+
+            let x = List::try_from(R!("list(list(matrix(c(0, 1), ncol = 2)))").unwrap()).unwrap();
+            let hints = Strings::from_values(["multilinestring"]);
+            let err = geoms_from_matrices(x, Some(hints)).unwrap_err();
+            assert!(err.to_string().starts_with("Cannot create a geometry from element 1: Component 1 "));
+        }
+    }
+
+    #[test]
+    fn geoms_from_matrices_checks_linestrings_and_integer_points() {
+        test! {
+            let x = List::try_from(R!("list(1:2, matrix(c(0L, 1L, 0L, 1L), ncol = 2))").unwrap()).unwrap();
+            let res = List::try_from(geoms_from_matrices(x, None).unwrap()).unwrap();
+
+            let pnt: Point = geometry(&res.elt(0).unwrap()).try_into().unwrap();
+            assert_eq!(pnt, Point::new(1.0, 2.0));
+            let lns: LineString = geometry(&res.elt(1).unwrap()).try_into().unwrap();
+            assert_eq!(lns, LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]));
+
+            // a single coordinate is not a linestring
+            let x = List::try_from(R!("list(matrix(c(0, 1), ncol = 2))").unwrap()).unwrap();
+            let err = geoms_from_matrices(x, None).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Cannot create a geometry from element 1: `x` must have at least 2 coordinates for a linestring. Found 1."
+            );
+
+            let x = List::try_from(R!("list(1:3)").unwrap()).unwrap();
+            let err = geoms_from_matrices(x, None).unwrap_err();
+            assert!(err.to_string().contains("a numeric vector of length 3"));
+        }
+    }
+
+    #[test]
+    fn multipoint_from_matrix_or_list() {
+        test! {
+            let opts = GeomOptions::default();
+            let expected = MultiPoint::from(vec![(0.0, 1.0), (2.0, 3.0)]);
+
+            let mpnt = |x: Robj| -> MultiPoint { geometry(&x).try_into().unwrap() };
+
+            let from_matrix = geom_multipoint_checked(R!("matrix(c(0, 2, 1, 3), ncol = 2)").unwrap(), &opts).unwrap();
+            assert_eq!(mpnt(from_matrix), expected);
+
+            let from_list = geom_multipoint_checked(R!("list(c(0, 1), c(2, 3))").unwrap(), &opts).unwrap();
+            assert_eq!(mpnt(from_list), expected);
+
+            let err = geom_multipoint_checked(R!("'a'").unwrap(), &opts).unwrap_err();
+            assert_eq!(err.to_string(), "MULTIPOINT must be a matrix or a list of points");
+
+            let err = geom_multipoint_checked(R!("list(c(0, 1), 'a')").unwrap(), &opts).unwrap_err();
+            assert_eq!(err.to_string(), "Point 2 of MULTIPOINT must be a numeric vector of length 2");
+        }
+    }
+
+    // a base data.frame and a tibble of the same coordinates
+    fn data_frames(x: &str, y: &str) -> [Robj; 2] {
+        let df = format!("data.frame(x = {x}, y = {y})");
+        let tbl = format!("structure({df}, class = c('tbl_df', 'tbl', 'data.frame'))");
+        [eval_string(&df).unwrap(), eval_string(&tbl).unwrap()]
+    }
+
+    #[test]
+    fn data_frames_in_every_constructor() {
+        test! {
+            let opts = GeomOptions::default();
+            let list = |x: Vec<Robj>| List::from_values(x);
+
+            for df in data_frames("c(0, 1, 1, 0)", "c(0L, 0L, 1L, 0L)") {
+                let coords = coords_from_robj(&df, &opts).unwrap();
+                assert_eq!(coords, matrix_to_coords(R!("matrix(c(0, 1, 1, 0, 0, 0, 1, 0), ncol = 2)").unwrap()));
+
+                let mpnt: MultiPoint = geometry(&geom_multipoint_checked(df.clone(), &opts).unwrap()).try_into().unwrap();
+                assert_eq!(mpnt.0.len(), 4);
+
+                assert_eq!(linestring(&geom_linestring_checked(df.clone(), &opts).unwrap()).0, coords);
+
+                let mlns = geom_multilinestring_checked(list(vec![df.clone()]), &opts).unwrap();
+                let mlns: MultiLineString = geometry(&mlns).try_into().unwrap();
+                assert_eq!(mlns.0[0].0, coords);
+
+                let poly = geom_polygon_checked(list(vec![df.clone()]), &opts).unwrap();
+                let poly: Polygon = geometry(&poly).try_into().unwrap();
+                assert_eq!(poly.exterior().0, coords);
+
+                let mpoly = geom_multipolygon_checked(list(vec![list(vec![df.clone()]).into()]), &opts).unwrap();
+                let mpoly: MultiPolygon = geometry(&mpoly).try_into().unwrap();
+                assert_eq!(mpoly.0[0].exterior().0, coords);
+
+                let lns = geom_linestrings(list(vec![df.clone()]), &opts).unwrap();
+                assert_eq!(linestring(&lns.as_list().unwrap().elt(0).unwrap()).0, coords);
+
+                geom_multipoints(list(vec![df.clone()]), &opts).unwrap();
+                geom_multilinestrings(list(vec![df.clone()]), &opts).unwrap();
+                geom_polygons(list(vec![list(vec![df.clone()]).into()]), &opts).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn data_frame_errors() {
+        test! {
+            let opts = GeomOptions::default();
+
+            for df in data_frames("numeric(0)", "numeric(0)") {
+                // zero rows are empty like a zero row matrix
+                assert!(linestring(&geom_linestring_checked(df, &opts).unwrap()).0.is_empty());
+            }
+
+            for df in data_frames("c(0, 1)", "c('a', 'b')") {
+                let err = geom_linestring_checked(df, &opts).unwrap_err();
+                assert!(err.to_string().starts_with("Column `y` of `x` must be numeric."));
+            }
+
+            let df = R!("data.frame(x = 1, y = 2, z = 3)").unwrap();
+            let err = geom_linestring_checked(df, &opts).unwrap_err();
+            assert_eq!(err.to_string(), "`x` must have exactly 2 columns for x and y coordinates. Found 3.");
+        }
+    }
+
+    #[test]
+    fn zero_row_matrices_are_empty() {
+        test! {
+            let opts = GeomOptions::default();
+            let list = |x: Vec<Robj>| List::from_values(x);
+
+            // `matrix(nrow = 0, ncol = 2)` is a logical matrix
+            for empty in [R!("matrix(numeric(0), ncol = 2)").unwrap(), R!("matrix(nrow = 0, ncol = 2)").unwrap()] {
+                let mpnt: MultiPoint = geometry(&geom_multipoint_checked(empty.clone(), &opts).unwrap()).try_into().unwrap();
+                assert!(mpnt.0.is_empty());
+
+                assert!(linestring(&geom_linestring_checked(empty.clone(), &opts).unwrap()).0.is_empty());
+
+                let mlns = geom_multilinestring_checked(list(vec![empty.clone()]), &opts).unwrap();
+                let mlns: MultiLineString = geometry(&mlns).try_into().unwrap();
+                assert!(mlns.0[0].0.is_empty());
+
+                let lns = geom_linestrings(list(vec![empty.clone()]), &opts).unwrap();
+                assert!(linestring(&lns.as_list().unwrap().elt(0).unwrap()).0.is_empty());
+
+                let err = geom_polygon_checked(list(vec![empty.clone()]), &opts).unwrap_err();
+                assert_eq!(err.to_string(), "Ring 1 (exterior) has no coordinates");
+
+                let exterior = R!("matrix(c(0, 1, 1, 0, 0, 0, 1, 0), ncol = 2)").unwrap();
+                let err = geom_polygon_checked(list(vec![exterior, empty.clone()]), &opts).unwrap_err();
+                assert_eq!(err.to_string(), "Ring 2 (interior 1) has no coordinates");
+            }
+        }
+    }
+
+    #[test]
+    fn list_to_polygon_with_holes() {
+        test! {
+            let exterior = RMatrix::new_matrix(4, 2, |r, c| [[0.0, 10.0, 10.0, 0.0], [0.0, 0.0, 10.0, 10.0]][c][r]);
+            let hole = RMatrix::new_matrix(3, 2, |r, c| [[2.0, 4.0, 2.0], [2.0, 2.0, 4.0]][c][r]);
+
+            let poly = list_to_polygon(list!(exterior.clone(), hole.clone())).unwrap();
+            assert_eq!(poly.exterior().0.len(), 5);
+            assert_eq!(poly.interiors().len(), 1);
+            assert_eq!(poly.interiors()[0], LineString::from(vec![(2.0, 2.0), (4.0, 2.0), (2.0, 4.0), (2.0, 2.0)]));
+
+            let polys = list_to_polygons(list!(list!(exterior.clone()), list!(exterior, hole))).unwrap();
+            assert_eq!(polys.len(), 2);
+            assert_eq!(polys[0].interiors().len(), 0);
+            assert_eq!(polys[1], poly);
+
+            let err = list_to_polygons(list!(list!(), 1)).unwrap_err();
+            assert_eq!(err.to_string(), "Polygon 1: A polygon must have at least 1 ring");
+        }
+    }
+
+    #[test]
+    fn report_rows_are_one_based() {
+        test! {
+            let (coords, rows) = matrix_to_coords_report(R!("matrix(c(0, NA, 2, 3, 0, 1, NaN, 3), ncol = 2)").unwrap()).unwrap();
+            assert_eq!(coords.len(), 4);
+            assert_eq!(rows, vec![2, 3]);
+
+            let err = matrix_to_coords_report(R!("matrix(1:3, ncol = 3)").unwrap()).unwrap_err();
+            assert_eq!(err.to_string(), "Matrix must have 2 columns for x and y coordinates. Found 3.");
+        }
+    }
+
+    #[test]
+    fn segments_from_a_million_rows() {
+        test! {
+            let n = 1_000_000;
+            let col = |offset: f64| Doubles::from_values((0..n).map(|i| i as f64 + offset));
+
+            let (x0, y0, x1, y1) = (col(0.0), col(0.5), col(1.0), col(1.5));
+            let mut y1 = y1;
+            y1.set_elt(10, Rfloat::na());
+
+            let res = geom_segments_xy(x0, y0, x1, y1).unwrap();
+            let res = res.as_list().unwrap();
+            assert_eq!(res.len(), n);
+            assert!(res.elt(10).unwrap().is_null());
+
+            for i in [0, 1, 999, 123_456, n - 1] {
+                let x = i as f64;
+                let expected = LineString::from(vec![(x, x + 0.5), (x + 1.0, x + 1.5)]);
+                assert_eq!(linestring(&res.elt(i).unwrap()), expected);
+            }
+
+            let err = geom_segments_xy(col(0.0), col(0.0), col(0.0), Doubles::new(2)).unwrap_err();
+            assert_eq!(err.to_string(), format!("`x0` and `y1` must be the same length. Found {n} and 2."));
+        }
+    }
+
+    #[test]
+    fn extent_to_rect_validates_bounds() {
+        let rect = extent_to_rect([0.0, 1.0, 2.0, 3.0]).unwrap();
+        assert_eq!(rect, Rect::new(coord! {x: 0.0, y: 1.0}, coord! {x: 2.0, y: 3.0}));
+
+        // zero area extents are valid
+        assert!(extent_to_rect([1.0, 1.0, 1.0, 1.0]).is_ok());
+
+        assert_eq!(extent_to_rect([0.0, f64::NAN, 2.0, 3.0]).unwrap_err(), "`ymin` is `NA`");
+        assert_eq!(
+            extent_to_rect([2.0, 1.0, 0.0, 3.0]).unwrap_err(),
+            "`xmin` (2) is greater than `xmax` (0)"
+        );
+        assert_eq!(
+            extent_to_rect([0.0, 4.0, 2.0, 3.0]).unwrap_err(),
+            "`ymin` (4) is greater than `ymax` (3)"
+        );
+    }
+
+    #[test]
+    fn bbox_to_polygon_is_counter_clockwise() {
+        use geo::Area;
+
+        test! {
+            let poly = bbox_to_polygon(R!("c(0, 0, 2, 1)").unwrap()).unwrap();
+            let poly: Polygon = geometry(&poly).try_into().unwrap();
+
+            assert!(poly.exterior().is_closed());
+            assert_eq!(poly.exterior().0.len(), 5);
+            assert!(poly.signed_area() > 0.0);
+
+            let bbox = R!("structure(c(xmin = 0, ymin = 0, xmax = 2, ymax = 1), class = 'bbox')").unwrap();
+            assert_eq!(geometry(&bbox_to_polygon(bbox).unwrap()), Geometry::from(poly));
+
+            assert!(bbox_to_polygon(R!("c(0, 0, 2)").unwrap()).is_err());
+        }
+    }
+}
 
@@ -0,0 +1,182 @@
+//! Coordinate reference systems of sfc objects
+//!
+//! sf stores the CRS of an sfc in its `crs` attribute. This is a list of class `crs`
+//! with elements `input` and `wkt`. The `Crs` struct mirrors that list so that the
+//! CRS can be carried alongside `Geom`s and reattached when converting back to sf.
+//! An `NA` crs, sf's default, is represented as `None`.
+use extendr_api::prelude::*;
+
+/// A coordinate reference system mirroring sf's `crs` object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crs {
+    /// the user input used to create the crs e.g. `"EPSG:4326"`
+    pub input: Option<String>,
+    /// the wkt representation of the crs
+    pub wkt: Option<String>,
+}
+
+impl Crs {
+    /// Create a `Crs` from an EPSG code and / or a wkt string. When an EPSG code
+    /// is provided the `input` is `"EPSG:{code}"`, otherwise the `wkt` is used.
+    pub fn new(epsg: Option<i32>, wkt: Option<&str>) -> Self {
+        let input = match epsg {
+            Some(code) => Some(format!("EPSG:{code}")),
+            None => wkt.map(String::from),
+        };
+
+        Crs { input, wkt: wkt.map(String::from) }
+    }
+
+    /// Returns `true` if neither `input` nor `wkt` are set which is sf's `NA` crs.
+    pub fn is_na(&self) -> bool {
+        self.input.is_none() && self.wkt.is_none()
+    }
+
+    /// The EPSG code of the crs if it can be found. The `input` is checked for an
+    /// `"EPSG:{code}"` string first followed by the top level `ID["EPSG",{code}]`
+    /// or `AUTHORITY["EPSG","{code}"]` of the `wkt`.
+    pub fn epsg(&self) -> Option<i32> {
+        let from_input = self.input.as_deref().and_then(|input| {
+            let (authority, code) = input.trim().split_once(':')?;
+            if authority.eq_ignore_ascii_case("epsg") {
+                code.trim().parse::<i32>().ok()
+            } else {
+                None
+            }
+        });
+
+        from_input.or_else(|| self.wkt.as_deref().and_then(epsg_from_wkt))
+    }
+}
+
+// the top level identifier of a wkt string is the last one
+fn epsg_from_wkt(wkt: &str) -> Option<i32> {
+    let start = ["ID[\"EPSG\",", "AUTHORITY[\"EPSG\","]
+        .iter()
+        .filter_map(|pattern| wkt.rfind(pattern).map(|i| i + pattern.len()))
+        .max()?;
+
+    let code = wkt[start..]
+        .trim_start_matches(|c: char| c == '"' || c.is_whitespace())
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?;
+
+    code.parse::<i32>().ok()
+}
+
+/// Creates an sf `crs` object.
+impl From<Crs> for Robj {
+    fn from(crs: Crs) -> Self {
+        let values = [Robj::from(crs.input.as_deref()), Robj::from(crs.wkt.as_deref())];
+
+        List::from_names_and_values(["input", "wkt"], values)
+            .unwrap()
+            .into_robj()
+            .set_class(["crs"])
+            .unwrap()
+    }
+}
+
+/// Reads the `crs` attribute of an sfc object. Returns `None` if the attribute
+/// is missing or is sf's `NA` crs.
+pub fn sfc_crs(x: &List) -> Result<Option<Crs>> {
+    let crs = match x.get_attrib("crs") {
+        Some(crs) if !crs.is_null() => crs,
+        _ => return Ok(None),
+    };
+
+    let crs = robj_to_crs(&crs)?;
+
+    if crs.is_na() {
+        Ok(None)
+    } else {
+        Ok(Some(crs))
+    }
+}
+
+/// Converts an sf `crs` object into a `Crs` struct.
+pub fn robj_to_crs(x: &Robj) -> Result<Crs> {
+    if !x.inherits("crs") {
+        return Err(Error::Other("Expected an object of class `crs`".to_string()));
+    }
+
+    let mut crs = Crs { input: None, wkt: None };
+
+    for (name, value) in List::try_from(x.clone())?.iter() {
+        let value = if value.is_na() {
+            None
+        } else {
+            value.as_str().map(String::from)
+        };
+
+        match name {
+            "input" => crs.input = value,
+            "wkt" => crs.wkt = value,
+            _ => (),
+        }
+    }
+
+    Ok(crs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WKT2: &str = r#"PROJCRS["WGS 84 / Pseudo-Mercator",
+    BASEGEOGCRS["WGS 84",
+        DATUM["World Geodetic System 1984",
+            ELLIPSOID["WGS 84",6378137,298.257223563]],
+        ID["EPSG",4326]],
+    CONVERSION["Popular Visualisation Pseudo-Mercator",
+        METHOD["Popular Visualisation Pseudo Mercator",
+            ID["EPSG",1024]]],
+    CS[Cartesian,2],
+    ID["EPSG",3857]]"#;
+
+    const WKT1: &str = r#"GEOGCS["WGS 84",
+    DATUM["WGS_1984",
+        SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],
+        AUTHORITY["EPSG","6326"]],
+    AUTHORITY["EPSG","4326"]]"#;
+
+    #[test]
+    fn epsg_codes() {
+        assert_eq!(Crs::new(Some(4326), None).epsg(), Some(4326));
+        assert_eq!(Crs { input: Some(" epsg: 4326".to_string()), wkt: None }.epsg(), Some(4326));
+
+        // the top level ID wins over the nested ones
+        let crs = Crs { input: Some("WGS 84 / Pseudo-Mercator".to_string()), wkt: Some(WKT2.to_string()) };
+        assert_eq!(crs.epsg(), Some(3857));
+
+        let crs = Crs { input: None, wkt: Some(WKT1.to_string()) };
+        assert_eq!(crs.epsg(), Some(4326));
+
+        assert_eq!(Crs { input: Some("ESRI:54009".to_string()), wkt: None }.epsg(), None);
+        assert_eq!(Crs { input: None, wkt: None }.epsg(), None);
+    }
+
+    #[test]
+    fn crs_round_trip() {
+        test! {
+            let crs = Crs::new(Some(3857), Some(WKT2));
+            let robj = Robj::from(crs.clone());
+            assert!(robj.inherits("crs"));
+            assert_eq!(robj_to_crs(&robj)?, crs);
+
+            let sfc = List::from_values([Robj::from(1.0)]);
+            let sfc = List::try_from(sfc.into_robj().set_attrib("crs", robj)?)?;
+            assert_eq!(sfc_crs(&sfc)?, Some(crs));
+
+            // an NA crs and a missing crs are None
+            let na = Robj::from(Crs { input: None, wkt: None });
+            assert!(robj_to_crs(&na)?.is_na());
+
+            let sfc = List::try_from(R!("structure(list(), crs = structure(list(input = NA_character_, wkt = NA_character_), class = 'crs'))")?)?;
+            assert_eq!(sfc_crs(&sfc)?, None);
+            assert_eq!(sfc_crs(&List::new(0))?, None);
+
+            assert!(robj_to_crs(&R!("list(input = 'EPSG:4326')")?).is_err());
+        }
+    }
+}
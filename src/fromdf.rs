@@ -0,0 +1,596 @@
+//! Construct geometries from long coordinate tables
+//!
+//! Coordinates are often stored in a "long" data.frame with one row per
+//! coordinate, as used by sfheaders. These functions take the x and y columns and
+//! one or more id columns and assemble the geometries from groups of consecutive
+//! rows that share an id. Ids may be integer, double, character, or factor
+//! vectors. Factors are grouped and named by their levels.
+//!
+//! The rows of a group must be contiguous. An id that appears again after a
+//! different id is an error rather than being grouped with its earlier rows, as
+//! reordering the rows would change the coordinate order of the geometry.
+//!
+//! Alternatively the groups can be given as run lengths: the number of consecutive
+//! coordinates that belong to each feature or part.
+use std::collections::HashSet;
+use std::ops::Range;
+
+use extendr_api::prelude::*;
+use geo_types::{Coord, LineString, MultiLineString, MultiPolygon, Polygon};
+use crate::{Geom, vctrs::as_rsgeo_vctr};
+
+/// Create an `rs_LINESTRING` vector from x and y coordinates grouped by `id`. One
+/// linestring is created for each group of consecutive rows in the order they
+/// appear and the vector is named by the ids. A group with a single row is an
+/// error unless `allow_degenerate` is true in which case it is a linestring with
+/// one coordinate. `NA` coordinates and ids are an error.
+pub fn linestrings_from_df(x: Doubles, y: Doubles, id: Robj, allow_degenerate: bool) -> Result<Robj> {
+    check_lengths(&x, &y, &[("id", id.len())])?;
+    check_coords(&x, &y)?;
+
+    let ids = id_keys(&id, "id")?;
+    let groups = id_groups(&ids, "id")?;
+
+    let mut res = List::new(groups.len());
+    let mut names = Vec::with_capacity(groups.len());
+
+    for (i, (key, rows)) in groups.into_iter().enumerate() {
+        if rows.len() < 2 && !allow_degenerate {
+            return Err(Error::Other(format!(
+                "linestring `{key}` has a single coordinate. Set `allow_degenerate` to create it anyway."
+            )));
+        }
+
+        let lns = Geom::from(LineString::new(group_coords(&x, &y, rows)))
+            .into_robj()
+            .set_class(["linestring", "Geom"])?;
+
+        res.set_elt(i, lns)?;
+        names.push(key);
+    }
+
+    as_rsgeo_vctr(res, "linestring")?.set_attrib("names", names)
+}
+
+/// Create an `rs_POLYGON` vector from x and y coordinates grouped by `polygon_id`
+/// and `ring_id`. Within each polygon the ring with `ring_id` `0` is the exterior
+/// and must come first; the remaining rings are holes. Rings that are not closed
+/// are closed by repeating their first coordinate. The vector is named by the
+/// polygon ids.
+///
+/// Errors name the offending ids when a polygon does not start with its exterior
+/// ring, such as a hole appearing before the exterior, or a ring has fewer than 4
+/// coordinates after closing. `NA` coordinates and ids are an error.
+pub fn polygons_from_df(x: Doubles, y: Doubles, polygon_id: Robj, ring_id: Robj) -> Result<Robj> {
+    check_lengths(&x, &y, &[("polygon_id", polygon_id.len()), ("ring_id", ring_id.len())])?;
+    check_coords(&x, &y)?;
+
+    let polygon_ids = id_keys(&polygon_id, "polygon_id")?;
+    let ring_ids = id_keys(&ring_id, "ring_id")?;
+    let groups = id_groups(&polygon_ids, "polygon_id")?;
+
+    let mut res = List::new(groups.len());
+    let mut names = Vec::with_capacity(groups.len());
+
+    for (i, (key, rows)) in groups.into_iter().enumerate() {
+        let poly = Geom::from(group_polygon(&x, &y, &ring_ids, rows, &key)?)
+            .into_robj()
+            .set_class(["polygon", "Geom"])?;
+
+        res.set_elt(i, poly)?;
+        names.push(key);
+    }
+
+    as_rsgeo_vctr(res, "polygon")?.set_attrib("names", names)
+}
+
+/// Create an `rs_MULTIPOLYGON` vector from x and y coordinates grouped by
+/// `multipolygon_id`, `polygon_id`, and `ring_id`. Each polygon is assembled as in
+/// `polygons_from_df()`. The vector is named by the multipolygon ids and errors
+/// include the multipolygon id.
+pub fn multipolygons_from_df(
+    x: Doubles,
+    y: Doubles,
+    multipolygon_id: Robj,
+    polygon_id: Robj,
+    ring_id: Robj,
+) -> Result<Robj> {
+    check_lengths(
+        &x,
+        &y,
+        &[
+            ("multipolygon_id", multipolygon_id.len()),
+            ("polygon_id", polygon_id.len()),
+            ("ring_id", ring_id.len()),
+        ],
+    )?;
+    check_coords(&x, &y)?;
+
+    let multipolygon_ids = id_keys(&multipolygon_id, "multipolygon_id")?;
+    let polygon_ids = id_keys(&polygon_id, "polygon_id")?;
+    let ring_ids = id_keys(&ring_id, "ring_id")?;
+    let groups = id_groups(&multipolygon_ids, "multipolygon_id")?;
+
+    let mut res = List::new(groups.len());
+    let mut names = Vec::with_capacity(groups.len());
+
+    for (i, (key, rows)) in groups.into_iter().enumerate() {
+        let in_multipolygon = |e: Error| Error::Other(format!("In multipolygon `{key}`: {e}"));
+        let offset = rows.start;
+
+        let polys = id_groups(&polygon_ids[rows], "polygon_id")
+            .map_err(in_multipolygon)?
+            .into_iter()
+            .map(|(polygon_id, poly_rows)| {
+                let rows = (poly_rows.start + offset)..(poly_rows.end + offset);
+                group_polygon(&x, &y, &ring_ids, rows, &polygon_id).map_err(in_multipolygon)
+            })
+            .collect::<Result<Vec<Polygon>>>()?;
+
+        let mpoly = Geom::from(MultiPolygon::new(polys))
+            .into_robj()
+            .set_class(["multipolygon", "Geom"])?;
+
+        res.set_elt(i, mpoly)?;
+        names.push(key);
+    }
+
+    as_rsgeo_vctr(res, "multipolygon")?.set_attrib("names", names)
+}
+
+// assembles the rings of one polygon from its rows
+pub(crate) fn group_polygon(
+    x: &Doubles,
+    y: &Doubles,
+    ring_ids: &[String],
+    rows: Range<usize>,
+    polygon_id: &str,
+) -> Result<Polygon> {
+    let offset = rows.start;
+    let rings = id_groups(&ring_ids[rows], "ring_id")
+        .map_err(|e| Error::Other(format!("In polygon `{polygon_id}`: {e}")))?;
+
+    let mut linestrings = Vec::with_capacity(rings.len());
+
+    for (j, (ring_id, ring_rows)) in rings.into_iter().enumerate() {
+        // ring ids are contiguous so the exterior can only appear once
+        if j == 0 && ring_id != "0" {
+            return Err(Error::Other(format!(
+                "Polygon `{polygon_id}` must start with its exterior ring `0`. Found ring `{ring_id}`."
+            )));
+        }
+
+        let rows = (ring_rows.start + offset)..(ring_rows.end + offset);
+        let coords = close_ring(group_coords(x, y, rows));
+
+        if coords.len() < 4 {
+            return Err(Error::Other(format!(
+                "Ring `{ring_id}` of polygon `{polygon_id}` has {} coordinates after closing. At least 4 are required.",
+                coords.len()
+            )));
+        }
+
+        linestrings.push(LineString::new(coords));
+    }
+
+    let exterior = linestrings.remove(0);
+    Ok(Polygon::new(exterior, linestrings))
+}
+
+/// Close a ring by repeating its first coordinate if it is not already closed.
+pub(crate) fn close_ring(mut x: Vec<Coord>) -> Vec<Coord> {
+    if let (Some(first), Some(last)) = (x.first(), x.last()) {
+        if first != last {
+            x.push(*first);
+        }
+    }
+
+    x
+}
+
+/// Create an `rs_LINESTRING` vector from x and y coordinates where `lengths` is the
+/// number of coordinates in each linestring. `sum(lengths)` must equal the number
+/// of coordinates. A length of `0` creates an empty linestring.
+pub fn linestrings_from_lengths(x: Doubles, y: Doubles, lengths: Integers) -> Result<Robj> {
+    check_lengths(&x, &y, &[])?;
+
+    let runs = length_runs(&lengths, x.len(), "lengths", "the number of coordinates")?;
+    let mut res = List::new(runs.len());
+
+    for (i, rows) in runs.into_iter().enumerate() {
+        let lns = Geom::from(LineString::new(group_coords(&x, &y, rows)))
+            .into_robj()
+            .set_class(["linestring", "Geom"])?;
+
+        res.set_elt(i, lns)?;
+    }
+
+    as_rsgeo_vctr(res, "linestring")
+}
+
+/// Create an `rs_MULTILINESTRING` vector from x and y coordinates where
+/// `part_lengths` is the number of coordinates in each linestring and `lengths` is
+/// the number of linestrings in each multilinestring. `sum(part_lengths)` must
+/// equal the number of coordinates and `sum(lengths)` the number of parts.
+pub fn multilinestrings_from_lengths(
+    x: Doubles,
+    y: Doubles,
+    lengths: Integers,
+    part_lengths: Integers,
+) -> Result<Robj> {
+    check_lengths(&x, &y, &[])?;
+
+    let parts = length_runs(&part_lengths, x.len(), "part_lengths", "the number of coordinates")?;
+    let features = length_runs(&lengths, parts.len(), "lengths", "the number of parts")?;
+    let mut res = List::new(features.len());
+
+    for (i, feature) in features.into_iter().enumerate() {
+        let lines = parts[feature]
+            .iter()
+            .map(|rows| LineString::new(group_coords(&x, &y, rows.clone())))
+            .collect::<Vec<LineString>>();
+
+        let mlns = Geom::from(MultiLineString::new(lines))
+            .into_robj()
+            .set_class(["multilinestring", "Geom"])?;
+
+        res.set_elt(i, mlns)?;
+    }
+
+    as_rsgeo_vctr(res, "multilinestring")
+}
+
+// converts run lengths into ranges, the lengths must sum to `n`
+fn length_runs(x: &Integers, n: usize, name: &str, total: &str) -> Result<Vec<Range<usize>>> {
+    let mut runs = Vec::with_capacity(x.len());
+    let mut start = 0;
+
+    for (i, len) in x.iter().enumerate() {
+        if len.is_na() {
+            return Err(Error::Other(format!("`{name}` cannot be `NA`. Found `NA` at position {}.", i + 1)));
+        }
+
+        if len.inner() < 0 {
+            return Err(Error::Other(format!(
+                "`{name}` must be non-negative. Found {} at position {}.",
+                len.inner(),
+                i + 1
+            )));
+        }
+
+        let end = start + len.inner() as usize;
+        runs.push(start..end);
+        start = end;
+    }
+
+    if start != n {
+        return Err(Error::Other(format!(
+            "`sum({name})` must equal {total}. Found {start} and {n}."
+        )));
+    }
+
+    Ok(runs)
+}
+
+// all columns must be the same length as `x`
+pub(crate) fn check_lengths(x: &Doubles, y: &Doubles, ids: &[(&str, usize)]) -> Result<()> {
+    let n = x.len();
+
+    if y.len() != n {
+        return Err(Error::Other(format!(
+            "`x` and `y` must be the same length. Found {} and {}.",
+            n,
+            y.len()
+        )));
+    }
+
+    for (name, len) in ids {
+        if *len != n {
+            return Err(Error::Other(format!(
+                "`{name}` must be the same length as `x`. Found {len} and {n}."
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn check_coords(x: &Doubles, y: &Doubles) -> Result<()> {
+    let na = x
+        .iter()
+        .zip(y.iter())
+        .position(|(xi, yi)| xi.is_na() || yi.is_na());
+
+    match na {
+        Some(i) => Err(Error::Other(format!("Coordinates cannot be `NA`. Found `NA` in row {}.", i + 1))),
+        None => Ok(()),
+    }
+}
+
+/// Convert an id column into string keys. Supports integer, double, character,
+/// and factor vectors. Factors are keyed by their levels rather than their integer
+/// codes. Errors on `NA` ids naming the row.
+pub(crate) fn id_keys(x: &Robj, name: &str) -> Result<Vec<String>> {
+    let na_error = |i: usize| {
+        Error::Other(format!("`{name}` cannot be `NA`. Found `NA` in row {}.", i + 1))
+    };
+
+    if x.inherits("factor") {
+        let codes = x.as_integer_slice().unwrap_or_default();
+        let levels = x
+            .get_attrib("levels")
+            .and_then(|levels| levels.as_string_vector())
+            .unwrap_or_default();
+
+        // codes are 1-based and NA_integer_ is i32::MIN
+        return codes
+            .iter()
+            .enumerate()
+            .map(|(i, code)| match levels.get((*code as usize).wrapping_sub(1)) {
+                Some(level) if *code > 0 => Ok(level.clone()),
+                _ => Err(na_error(i)),
+            })
+            .collect();
+    }
+
+    if let Some(ids) = x.as_integer_slice() {
+        // NA_integer_ is i32::MIN
+        return ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| if *id == i32::MIN { Err(na_error(i)) } else { Ok(id.to_string()) })
+            .collect();
+    }
+
+    if let Some(ids) = x.as_real_slice() {
+        return ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| if id.is_nan() { Err(na_error(i)) } else { Ok(id.to_string()) })
+            .collect();
+    }
+
+    if let Ok(ids) = Strings::try_from(x.clone()) {
+        return ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| if id.is_na() { Err(na_error(i)) } else { Ok(id.to_string()) })
+            .collect();
+    }
+
+    Err(Error::Other(format!("`{name}` must be an integer, double, or character vector")))
+}
+
+/// Split ids into groups of consecutive rows. Errors if an id reappears after a
+/// different id.
+pub(crate) fn id_groups(ids: &[String], name: &str) -> Result<Vec<(String, Range<usize>)>> {
+    let mut groups: Vec<(String, Range<usize>)> = Vec::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+
+    for (i, id) in ids.iter().enumerate() {
+        match groups.last_mut() {
+            Some((key, rows)) if key == id => rows.end = i + 1,
+            _ => {
+                if !seen.insert(id.as_str()) {
+                    return Err(Error::Other(format!(
+                        "`{name}` must be contiguous. `{id}` appears again in row {}.",
+                        i + 1
+                    )));
+                }
+
+                groups.push((id.clone(), i..i + 1));
+            }
+        }
+    }
+
+    Ok(groups)
+}
+
+pub(crate) fn group_coords(x: &Doubles, y: &Doubles, rows: Range<usize>) -> Vec<Coord> {
+    rows.map(|i| Coord { x: x[i].inner(), y: y[i].inner() }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(x: &[&str]) -> Vec<String> {
+        x.iter().map(|x| x.to_string()).collect()
+    }
+
+    fn names(x: &Robj) -> Vec<String> {
+        x.names().unwrap().map(String::from).collect()
+    }
+
+    #[test]
+    fn id_groups_are_contiguous() {
+        let groups = id_groups(&keys(&["b", "b", "a", "c", "c", "c"]), "id").unwrap();
+        assert_eq!(
+            groups,
+            vec![(String::from("b"), 0..2), (String::from("a"), 2..3), (String::from("c"), 3..6)]
+        );
+
+        let err = id_groups(&keys(&["a", "b", "a"]), "id").unwrap_err().to_string();
+        assert!(err.contains("`a` appears again in row 3"));
+
+        assert!(id_groups(&[], "id").unwrap().is_empty());
+    }
+
+    #[test]
+    fn linestrings_from_df_groups_by_id() {
+        test! {
+            let x = Doubles::from_values([0.0, 1.0, 2.0, 3.0, 4.0]);
+            let y = Doubles::from_values([0.0, 1.0, 2.0, 3.0, 4.0]);
+
+            // groups are kept in the order they appear, not sorted
+            let res = linestrings_from_df(x.clone(), y.clone(), R!("c('z', 'z', 'a', 'a', 'a')").unwrap(), false).unwrap();
+            assert_eq!(names(&res), vec!["z", "a"]);
+
+            // unsorted ids that are not contiguous are an error
+            let err = linestrings_from_df(x.clone(), y.clone(), R!("c(2L, 2L, 1L, 1L, 2L)").unwrap(), false).unwrap_err();
+            assert!(err.to_string().contains("`2` appears again in row 5"));
+
+            // a single row group needs `allow_degenerate`
+            let id = R!("c(1, 1, 2, 3, 3)").unwrap();
+            assert!(linestrings_from_df(x.clone(), y.clone(), id.clone(), false).is_err());
+            let res = linestrings_from_df(x.clone(), y.clone(), id, true).unwrap();
+            assert_eq!(names(&res), vec!["1", "2", "3"]);
+
+            // NA coordinates and ids name the row
+            let na = Doubles::try_from(R!("c(0, 1, NA, 3, 4)").unwrap()).unwrap();
+            let err = linestrings_from_df(x.clone(), na, R!("rep(1L, 5)").unwrap(), false).unwrap_err();
+            assert!(err.to_string().contains("row 3"));
+
+            let err = linestrings_from_df(x.clone(), y.clone(), R!("c(1L, NA, 1L, 1L, 1L)").unwrap(), false).unwrap_err();
+            assert!(err.to_string().contains("`id` cannot be `NA`. Found `NA` in row 2."));
+        }
+    }
+
+    #[test]
+    fn factor_ids_are_keyed_by_level() {
+        test! {
+            let x = Doubles::from_values([0.0, 1.0, 2.0, 3.0]);
+            let id = R!("factor(c('b', 'b', 'a', 'a'))").unwrap();
+
+            assert_eq!(id_keys(&id, "id").unwrap(), keys(&["b", "b", "a", "a"]));
+
+            let res = linestrings_from_df(x.clone(), x, id, false).unwrap();
+            assert_eq!(names(&res), vec!["b", "a"]);
+
+            let err = id_keys(&R!("factor(c('a', NA))").unwrap(), "id").unwrap_err();
+            assert!(err.to_string().contains("row 2"));
+        }
+    }
+
+    #[test]
+    fn close_ring_repeats_the_first_coordinate() {
+        let open = vec![Coord { x: 0.0, y: 0.0 }, Coord { x: 1.0, y: 0.0 }, Coord { x: 1.0, y: 1.0 }];
+        let closed = close_ring(open.clone());
+
+        assert_eq!(closed.len(), 4);
+        assert_eq!(closed[3], open[0]);
+        assert_eq!(close_ring(closed.clone()), closed);
+        assert!(close_ring(vec![]).is_empty());
+    }
+
+    #[test]
+    fn polygons_from_df_rings() {
+        test! {
+            // an open exterior with an open hole
+            let x = Doubles::try_from(R!("c(0, 10, 10, 0, 2, 4, 4)").unwrap()).unwrap();
+            let y = Doubles::try_from(R!("c(0, 0, 10, 10, 2, 2, 4)").unwrap()).unwrap();
+            let polygon_id = R!("rep('a', 7)").unwrap();
+
+            let res = polygons_from_df(x.clone(), y.clone(), polygon_id.clone(), R!("c(0, 0, 0, 0, 1, 1, 1)").unwrap()).unwrap();
+            assert_eq!(names(&res), vec!["a"]);
+
+            let poly: Polygon = crate::geoms_from_list(List::try_from(res).unwrap())[0]
+                .clone()
+                .unwrap()
+                .geom
+                .try_into()
+                .unwrap();
+            assert!(poly.exterior().is_closed());
+            assert_eq!(poly.exterior().0.len(), 5);
+            assert_eq!(poly.interiors()[0].0.len(), 4);
+
+            // a hole before the exterior
+            let err = polygons_from_df(x.clone(), y.clone(), polygon_id.clone(), R!("c(1, 1, 1, 0, 0, 0, 0)").unwrap())
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("Polygon `a` must start with its exterior ring `0`. Found ring `1`."));
+
+            // a ring with 2 coordinates has 3 after closing
+            let err = polygons_from_df(x, y, polygon_id, R!("c(0, 0, 0, 0, 0, 1, 1)").unwrap())
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("Ring `1` of polygon `a` has 3 coordinates"));
+        }
+    }
+
+    #[test]
+    fn multipolygon_round_trip() {
+        test! {
+            let square = |x: f64, size: f64| {
+                LineString::from(vec![(x, 0.0), (x + size, 0.0), (x + size, size), (x, size), (x, 0.0)])
+            };
+
+            let mpoly = MultiPolygon::new(vec![
+                Polygon::new(square(0.0, 10.0), vec![square(1.0, 2.0), square(5.0, 2.0)]),
+                Polygon::new(square(20.0, 5.0), vec![]),
+            ]);
+
+            // explode into a long table with one row per coordinate
+            let coords = crate::query::rsgeo_coordinates(List::from_values([Geom::from(mpoly.clone()).into_robj()]));
+            let coords = List::try_from(coords).unwrap();
+            let col = |i: usize| coords.elt(i).unwrap();
+
+            let (x, y) = (col(4).as_real_vector().unwrap(), col(5).as_real_vector().unwrap());
+            let polygon_id = col(2).as_integer_vector().unwrap();
+            let mut ring_id = col(3).as_integer_vector().unwrap();
+
+            let n = x.len();
+            let res = multipolygons_from_df(
+                Doubles::from_values(x.clone()),
+                Doubles::from_values(y.clone()),
+                Robj::from(vec!["m"; n]),
+                Robj::from(polygon_id.clone()),
+                Robj::from(ring_id.clone()),
+            )
+            .unwrap();
+
+            let geoms = crate::geoms_from_list(List::try_from(res).unwrap());
+            assert_eq!(geoms[0].clone().unwrap().geom, mpoly.clone().into());
+
+            // errors name the multipolygon, polygon, and ring
+            ring_id[n - 2] = 1;
+            ring_id[n - 1] = 1;
+            let err = multipolygons_from_df(
+                Doubles::from_values(x),
+                Doubles::from_values(y),
+                Robj::from(vec!["m"; n]),
+                Robj::from(polygon_id),
+                Robj::from(ring_id),
+            )
+            .unwrap_err()
+            .to_string();
+            assert!(err.contains("In multipolygon `m`: Ring `1` of polygon `2` has 3 coordinates"), "{err}");
+        }
+    }
+
+    #[test]
+    fn geometries_from_lengths() {
+        test! {
+            let x = Doubles::from_values([0.0, 1.0, 2.0, 3.0, 4.0]);
+            let y = Doubles::from_values([5.0, 6.0, 7.0, 8.0, 9.0]);
+
+            // a zero length part is an empty linestring
+            let res = linestrings_from_lengths(x.clone(), y.clone(), Integers::from_values([2, 0, 3])).unwrap();
+            let geoms = crate::geoms_from_list(List::try_from(res).unwrap());
+            assert_eq!(geoms.len(), 3);
+            assert_eq!(geoms[0].clone().unwrap().geom, LineString::from(vec![(0.0, 5.0), (1.0, 6.0)]).into());
+            assert_eq!(geoms[1].clone().unwrap().geom, LineString::new(vec![]).into());
+
+            let res = multilinestrings_from_lengths(
+                x.clone(),
+                y.clone(),
+                Integers::from_values([2, 1]),
+                Integers::from_values([2, 0, 3]),
+            )
+            .unwrap();
+            let geoms = crate::geoms_from_list(List::try_from(res).unwrap());
+            let mlns: MultiLineString = geoms[0].clone().unwrap().geom.try_into().unwrap();
+            assert_eq!(mlns.0.len(), 2);
+            assert!(mlns.0[1].0.is_empty());
+
+            // the lengths must add up
+            let err = linestrings_from_lengths(x.clone(), y.clone(), Integers::from_values([2, 2])).unwrap_err();
+            assert!(err.to_string().contains("`sum(lengths)` must equal the number of coordinates. Found 4 and 5."));
+
+            let err = multilinestrings_from_lengths(x, y, Integers::from_values([4]), Integers::from_values([2, 0, 3]))
+                .unwrap_err();
+            assert!(err.to_string().contains("`sum(lengths)` must equal the number of parts. Found 4 and 3."));
+        }
+    }
+}
+
@@ -0,0 +1,359 @@
+//! Create geometries from GeoJSON
+//!
+//! Available with the `geojson` feature. Strings are parsed using the
+//! [geojson](https://docs.rs/geojson) crate. Each string may be a GeoJSON geometry
+//! or a Feature. `NA` strings, `null`, and Features with a `null` geometry are
+//! missing geometries. A `Point` with an empty coordinates array is an empty point.
+use extendr_api::prelude::*;
+use geo_types::{
+    Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon,
+    Point, Polygon,
+};
+use geojson::{GeoJson, JsonObject, JsonValue, Position, Value};
+use crate::Geom;
+
+/// Parse a character vector of GeoJSON geometries into a `Vec<Option<Geom>>`.
+/// Returns an error with the position of the first string that cannot be parsed.
+pub fn geoms_from_geojson(x: Strings) -> Result<Vec<Option<Geom>>> {
+    x.iter()
+        .enumerate()
+        .map(|(i, json)| {
+            if json.is_na() {
+                return Ok(None);
+            }
+
+            geojson_to_geom(json.as_str())
+                .map_err(|e| Error::Other(format!("Failed to parse GeoJSON at position {}\n{e}", i + 1)))
+        })
+        .collect()
+}
+
+/// Parse a single FeatureCollection string. Returns the geometries of the features
+/// and a named list of property columns. Properties are columns in the order they
+/// first appear. Numeric, logical, and string properties become double, logical,
+/// and character columns. Columns with mixed or nested values are character vectors
+/// of the JSON values. Missing and `null` properties are `NA`.
+pub fn geoms_from_feature_collection(x: &str) -> Result<(Vec<Option<Geom>>, List)> {
+    let collection = match x.parse::<GeoJson>() {
+        Ok(GeoJson::FeatureCollection(fc)) => fc,
+        Ok(_) => return Err(Error::Other("Expected a GeoJSON FeatureCollection".to_string())),
+        Err(e) => return Err(Error::Other(format!("Failed to parse GeoJSON\n{e}"))),
+    };
+
+    let mut geoms = Vec::with_capacity(collection.features.len());
+    let mut properties = Vec::with_capacity(collection.features.len());
+
+    for (i, feature) in collection.features.into_iter().enumerate() {
+        let geom = feature
+            .geometry
+            .map(|geom| value_to_geometry(&geom.value).map(Geom::from))
+            .transpose()
+            .map_err(|e| Error::Other(format!("Failed to parse GeoJSON feature {}\n{e}", i + 1)))?;
+
+        geoms.push(geom);
+        properties.push(feature.properties);
+    }
+
+    Ok((geoms, property_columns(&properties)?))
+}
+
+fn geojson_to_geom(x: &str) -> std::result::Result<Option<Geom>, String> {
+    let x = x.trim();
+
+    if x == "null" {
+        return Ok(None);
+    }
+
+    let geometry = match x.parse::<GeoJson>().map_err(|e| e.to_string())? {
+        GeoJson::Geometry(geom) => Some(geom),
+        GeoJson::Feature(feature) => feature.geometry,
+        GeoJson::FeatureCollection(_) => {
+            return Err("Expected a GeoJSON geometry but found a FeatureCollection".to_string())
+        }
+    };
+
+    geometry
+        .map(|geom| value_to_geometry(&geom.value).map(Geom::from))
+        .transpose()
+}
+
+fn value_to_geometry(x: &Value) -> std::result::Result<Geometry, String> {
+    let res: Geometry = match x {
+        // sf uses c(NA, NA) for an empty point
+        Value::Point(pos) if pos.is_empty() => Point::new(f64::NAN, f64::NAN).into(),
+        Value::Point(pos) => Point::from(position_to_coord(pos)?).into(),
+        Value::MultiPoint(pos) => MultiPoint::new(
+            pos.iter()
+                .map(|p| position_to_coord(p).map(Point::from))
+                .collect::<std::result::Result<_, _>>()?,
+        )
+        .into(),
+        Value::LineString(lns) => positions_to_linestring(lns)?.into(),
+        Value::MultiLineString(lns) => MultiLineString::new(
+            lns.iter()
+                .map(|l| positions_to_linestring(l))
+                .collect::<std::result::Result<_, _>>()?,
+        )
+        .into(),
+        Value::Polygon(rings) => rings_to_polygon(rings)?.into(),
+        Value::MultiPolygon(polys) => MultiPolygon::new(
+            polys
+                .iter()
+                .map(|p| rings_to_polygon(p))
+                .collect::<std::result::Result<_, _>>()?,
+        )
+        .into(),
+        Value::GeometryCollection(geoms) => GeometryCollection::new_from(
+            geoms
+                .iter()
+                .map(|g| value_to_geometry(&g.value))
+                .collect::<std::result::Result<_, _>>()?,
+        )
+        .into(),
+    };
+
+    Ok(res)
+}
+
+// positions may have a third or fourth element which are dropped
+fn position_to_coord(x: &Position) -> std::result::Result<Coord, String> {
+    if x.len() < 2 {
+        return Err(format!("A position must have at least 2 elements, found {}", x.len()));
+    }
+
+    Ok(Coord { x: x[0], y: x[1] })
+}
+
+fn positions_to_linestring(x: &[Position]) -> std::result::Result<LineString, String> {
+    x.iter()
+        .map(position_to_coord)
+        .collect::<std::result::Result<Vec<Coord>, _>>()
+        .map(LineString::new)
+}
+
+fn rings_to_polygon(x: &[Vec<Position>]) -> std::result::Result<Polygon, String> {
+    let mut rings = x
+        .iter()
+        .map(|ring| positions_to_linestring(ring))
+        .collect::<std::result::Result<Vec<LineString>, _>>()?
+        .into_iter();
+
+    let exterior = rings.next().unwrap_or_else(|| LineString::new(vec![]));
+    Ok(Polygon::new(exterior, rings.collect()))
+}
+
+// collects the properties of each feature into named columns
+fn property_columns(x: &[Option<JsonObject>]) -> Result<List> {
+    let mut keys: Vec<&String> = Vec::new();
+
+    for props in x.iter().flatten() {
+        for key in props.keys() {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+
+    let columns = keys
+        .iter()
+        .map(|key| {
+            let values = x
+                .iter()
+                .map(|props| props.as_ref().and_then(|p| p.get(key.as_str())))
+                .map(|v| v.filter(|v| !v.is_null()))
+                .collect::<Vec<Option<&JsonValue>>>();
+
+            property_column(&values)
+        })
+        .collect::<Vec<Robj>>();
+
+    List::from_names_and_values(keys, columns)
+}
+
+fn property_column(x: &[Option<&JsonValue>]) -> Robj {
+    let present = || x.iter().flatten();
+
+    if present().all(|v| v.is_number()) {
+        Doubles::from_values(x.iter().map(|v| match v.and_then(|v| v.as_f64()) {
+            Some(v) => Rfloat::from(v),
+            None => Rfloat::na(),
+        }))
+        .into_robj()
+    } else if present().all(|v| v.is_boolean()) {
+        Logicals::from_values(x.iter().map(|v| match v.and_then(|v| v.as_bool()) {
+            Some(v) => Rbool::from(v),
+            None => Rbool::na(),
+        }))
+        .into_robj()
+    } else {
+        x.iter()
+            .map(|v| match v {
+                Some(JsonValue::String(v)) => Some(v.clone()),
+                Some(v) => Some(v.to_string()),
+                None => None,
+            })
+            .collect::<Vec<Option<String>>>()
+            .into_robj()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::HasDimensions;
+
+    // there is no GeoJSON writer in this crate so these compare against
+    // hand-written GeoJSON rather than round tripping
+    fn parse(x: &str) -> Option<Geometry> {
+        geojson_to_geom(x).unwrap().map(|geom| geom.geom)
+    }
+
+    fn square() -> LineString {
+        LineString::from(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)])
+    }
+
+    fn hole() -> LineString {
+        LineString::from(vec![(0.2, 0.2), (0.4, 0.2), (0.4, 0.4), (0.2, 0.2)])
+    }
+
+    #[test]
+    fn every_geometry_type() {
+        let lns = LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]);
+        let poly = Polygon::new(square(), vec![hole()]);
+
+        assert_eq!(
+            parse(r#"{"type": "Point", "coordinates": [1, 2, 3]}"#),
+            Some(Point::new(1.0, 2.0).into())
+        );
+        assert_eq!(
+            parse(r#"{"type": "MultiPoint", "coordinates": [[0, 0], [1, 1]]}"#),
+            Some(MultiPoint::from(vec![(0.0, 0.0), (1.0, 1.0)]).into())
+        );
+        assert_eq!(
+            parse(r#"{"type": "LineString", "coordinates": [[0, 0], [1, 1]]}"#),
+            Some(lns.clone().into())
+        );
+        assert_eq!(
+            parse(r#"{"type": "MultiLineString", "coordinates": [[[0, 0], [1, 1]], [[0, 0], [1, 1]]]}"#),
+            Some(MultiLineString::new(vec![lns.clone(), lns.clone()]).into())
+        );
+        assert_eq!(
+            parse(
+                r#"{"type": "Polygon", "coordinates": [
+                    [[0, 0], [1, 0], [1, 1], [0, 0]],
+                    [[0.2, 0.2], [0.4, 0.2], [0.4, 0.4], [0.2, 0.2]]
+                ]}"#
+            ),
+            Some(poly.clone().into())
+        );
+        assert_eq!(
+            parse(
+                r#"{"type": "MultiPolygon", "coordinates": [
+                    [[[0, 0], [1, 0], [1, 1], [0, 0]], [[0.2, 0.2], [0.4, 0.2], [0.4, 0.4], [0.2, 0.2]]],
+                    [[[0, 0], [1, 0], [1, 1], [0, 0]]]
+                ]}"#
+            ),
+            Some(MultiPolygon::new(vec![poly.clone(), Polygon::new(square(), vec![])]).into())
+        );
+        assert_eq!(
+            parse(
+                r#"{"type": "GeometryCollection", "geometries": [
+                    {"type": "Point", "coordinates": [1, 2]},
+                    {"type": "GeometryCollection", "geometries": [
+                        {"type": "LineString", "coordinates": [[0, 0], [1, 1]]}
+                    ]}
+                ]}"#
+            ),
+            Some(
+                GeometryCollection::new_from(vec![
+                    Point::new(1.0, 2.0).into(),
+                    GeometryCollection::new_from(vec![lns.clone().into()]).into(),
+                ])
+                .into()
+            )
+        );
+
+        // a Feature is its geometry
+        assert_eq!(
+            parse(r#"{"type": "Feature", "properties": {}, "geometry": {"type": "Point", "coordinates": [1, 2]}}"#),
+            Some(Point::new(1.0, 2.0).into())
+        );
+    }
+
+    #[test]
+    fn nulls_and_empty_coordinates() {
+        assert_eq!(parse(" null "), None);
+        assert_eq!(parse(r#"{"type": "Feature", "properties": null, "geometry": null}"#), None);
+
+        match parse(r#"{"type": "Point", "coordinates": []}"#) {
+            Some(Geometry::Point(pnt)) => assert!(pnt.x().is_nan() && pnt.y().is_nan()),
+            x => panic!("expected an empty POINT, found {x:?}"),
+        }
+
+        for x in [
+            r#"{"type": "MultiPoint", "coordinates": []}"#,
+            r#"{"type": "LineString", "coordinates": []}"#,
+            r#"{"type": "Polygon", "coordinates": []}"#,
+            r#"{"type": "MultiPolygon", "coordinates": []}"#,
+            r#"{"type": "GeometryCollection", "geometries": []}"#,
+        ] {
+            assert!(parse(x).unwrap().is_empty(), "{x}");
+        }
+
+        assert!(geojson_to_geom(r#"{"type": "Point", "coordinates": [1]}"#).is_err());
+        assert!(geojson_to_geom(r#"{"type": "FeatureCollection", "features": []}"#).is_err());
+    }
+
+    #[test]
+    fn errors_report_the_position() {
+        test! {
+            let x = Strings::try_from(R!("c('{\"type\": \"Point\", \"coordinates\": [1, 2]}', NA)")?)?;
+            let geoms = geoms_from_geojson(x)?;
+            assert_eq!(geoms.len(), 2);
+            assert_eq!(geoms[0].as_ref().map(|g| g.geom.clone()), Some(Point::new(1.0, 2.0).into()));
+            assert!(geoms[1].is_none());
+
+            let x = Strings::try_from(R!("c('null', '{\"type\": \"Point\"')")?)?;
+            let msg = geoms_from_geojson(x).unwrap_err().to_string();
+            assert!(msg.contains("position 2"), "{msg}");
+        }
+    }
+
+    #[test]
+    fn feature_collection_properties() {
+        test! {
+            let x = r#"{"type": "FeatureCollection", "features": [
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [1, 2]},
+                    "properties": {"n": 1, "flag": true, "mixed": 1, "name": "a"}},
+                {"type": "Feature", "geometry": null,
+                    "properties": {"n": null, "flag": false, "mixed": "b", "extra": [1, 2]}},
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [3, 4]},
+                    "properties": null}
+            ]}"#;
+
+            let (geoms, props) = geoms_from_feature_collection(x)?;
+            assert_eq!(geoms.len(), 3);
+            assert_eq!(geoms[0].as_ref().map(|g| g.geom.clone()), Some(Point::new(1.0, 2.0).into()));
+            assert!(geoms[1].is_none());
+            assert_eq!(geoms[2].as_ref().map(|g| g.geom.clone()), Some(Point::new(3.0, 4.0).into()));
+
+            // columns are in the order they first appear
+            let names = props.names().unwrap().collect::<Vec<&str>>();
+            assert_eq!(names, vec!["n", "flag", "mixed", "name", "extra"]);
+
+            assert_eq!(props.elt(0)?, R!("c(1, NA, NA)")?);
+            assert_eq!(props.elt(1)?, R!("c(TRUE, FALSE, NA)")?);
+            assert_eq!(props.elt(2)?, R!("c('1', 'b', NA)")?);
+            assert_eq!(props.elt(3)?, R!("c('a', NA, NA)")?);
+            assert_eq!(props.elt(4)?, R!("c(NA, '[1,2]', NA)")?);
+
+            assert!(geoms_from_feature_collection(r#"{"type": "Point", "coordinates": [1, 2]}"#).is_err());
+
+            let bad = r#"{"type": "FeatureCollection", "features": [
+                {"type": "Feature", "properties": null, "geometry": {"type": "Point", "coordinates": [1]}}
+            ]}"#;
+            let msg = geoms_from_feature_collection(bad).unwrap_err().to_string();
+            assert!(msg.contains("feature 1"), "{msg}");
+        }
+    }
+}
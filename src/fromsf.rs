@@ -22,8 +22,11 @@
 
 use extendr_api::prelude::*;
 
-use crate::{Geom, vctrs::{determine_geoms_class}};
-use geo_types::Geometry;
+use geo::{CoordsIter, HasDimensions, MapCoordsInPlace};
+use crate::{Geom, geometry_type, meta::SfcMeta, vctrs::{determine_geoms_class, geom_class, geom_to_robj}};
+use geo_types::{
+    Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon, Rect
+};
 
 use std::{
     error::Error,
@@ -31,18 +34,47 @@ use std::{
 };
 
 
+/// Converts an sfc object into an rsgeo vctr. The names of `x`, if any,
+/// are kept as the names of the rsgeo vctr.
 pub fn sfc_to_rsgeo(x: List) -> Robj {
-    let mut rsgeo = List::new(x.len());
+    let names = x.get_attrib("names");
+    let res = sfc_to_rsgeo_unnamed(x);
 
-    for (i, (_, obj)) in x.iter().enumerate() {
-        rsgeo.set_elt(i, sfg_to_rsgeo(obj)).unwrap();
+    match names {
+        Some(names) if !names.is_null() => res.set_attrib("names", names).unwrap(),
+        _ => res,
+    }
+}
+
+fn sfc_to_rsgeo_unnamed(x: List) -> Robj {
+    if x.inherits("rsgeo") {
+        return x.into_robj();
+    }
+
+    // homogeneous sfc keep their type even if every geometry is missing
+    let precision = sfc_precision(&x);
+    let (geoms, cls) = match sfc_fast_path(&x, precision) {
+        Some((geoms, cls)) => (geoms, Some(cls)),
+        None => (convert_sfc(x, precision), None),
+    };
+
+    let mut rsgeo = List::new(geoms.len());
+
+    for (i, geom) in geoms.into_iter().enumerate() {
+        if let Some(geom) = geom {
+            rsgeo.set_elt(i, geom_to_robj(geom)).unwrap();
+        }
     }
 
     // see https://github.com/extendr/extendr/pull/540
     // let rsgeo = x
     //     .into_iter()
     //     .map(|(_, robj)| sfg_to_rsgeo(robj)).collect::<List>();   
-    let cls = determine_geoms_class(&rsgeo);
+    let cls = match cls {
+        Some(cls) => geom_class(cls),
+        None => determine_geoms_class(&rsgeo),
+    };
+
     rsgeo
         .set_class(cls)
         .unwrap()
@@ -54,38 +86,627 @@ pub fn sfc_to_rsgeo(x: List) -> Robj {
 
 /// Given an sfc object, creates a vector of `Option<Geometry>`. NULL geometries are stored
 /// as `None` and non-null are `Some(Geometry)`. Match on the result to get the underlying
-/// geo-types geometry object or handle null geometry. 
+/// geo-types geometry object or handle null geometry. Coordinates are snapped to the
+/// sfc's precision as in `sfc_to_geoms()`.
 pub fn sfc_to_geometry(x: List) -> Vec<Option<Geometry>> {
-    x
+    sfc_to_geoms(x)
         .into_iter()
-        .map(|(_, robj)| {
-            let geo = sfg_to_geom(robj);
-            match geo {
-                Ok(g) => Some(g.geom),
-                Err(_) => None
-            }
-        }).collect::<Vec<Option<Geometry>>>()
+        .map(|geom| geom.map(|geom| geom.geom))
+        .collect::<Vec<Option<Geometry>>>()
 }
 
 
+/// Returns the names of an sfc object. Unnamed sfc objects return `None`.
+/// Use alongside `sfc_to_geoms()` to keep feature identifiers.
+pub fn sfc_names(x: &List) -> Option<Vec<String>> {
+    x
+        .get_attrib("names")
+        .and_then(|names| names.as_string_vector())
+}
+
+/// Given an sfc object, creates a vector of `Option<Geom>`. NULL geometries are stored
+/// as `None`. If the sfc has a non-zero `precision` attribute the coordinates are
+/// snapped to it as they would be by sf. See `sfc_to_geoms_precision()`.
 pub fn sfc_to_geoms(x: List) -> Vec<Option<Geom>> {
+    sfc_to_geoms_precision(x, false).0
+}
+
+/// Converts an sfc object into a vector of `Option<Geom>` and returns its `precision`
+/// attribute so that it can be restored with `tosf::geoms_to_sfc_precision()`.
+///
+/// sf uses the precision as a coordinate grid. A positive precision snaps each
+/// coordinate to `round(x * precision) / precision` and a negative precision rounds
+/// to single (32 bit) precision. A precision of `0` leaves coordinates unchanged.
+/// Set `ignore_precision` to `true` to keep the raw coordinates.
+pub fn sfc_to_geoms_precision(x: List, ignore_precision: bool) -> (Vec<Option<Geom>>, f64) {
+    let precision = sfc_precision(&x);
+    let snap = if ignore_precision { 0.0 } else { precision };
+
+    (convert_sfc(x, snap), precision)
+}
+
+/// Converts an sfc object into a vector of `Option<Geom>` and captures its attributes
+/// in an `SfcMeta` so that they can be reapplied with `tosf::geoms_to_sfc_with_meta()`.
+/// Coordinates are snapped to the sfc's precision as in `sfc_to_geoms()`.
+pub fn sfc_to_geoms_with_meta(x: List) -> Result<(Vec<Option<Geom>>, SfcMeta), Box<dyn Error>> {
+    let meta = SfcMeta::from_sfc(&x)?;
+    let geoms = sfc_to_geoms(x);
+    Ok((geoms, meta))
+}
+
+/// Returns the `precision` attribute of an sfc object or `0` if it is missing.
+pub fn sfc_precision(x: &List) -> f64 {
     x
-        .into_iter()
+        .get_attrib("precision")
+        .and_then(|precision| precision.as_real())
+        .unwrap_or(0.0)
+}
+
+/// Snap the coordinates of a `Geom` to the precision grid used by sf. A precision of
+/// `0` leaves the coordinates unchanged.
+pub fn snap_to_precision(x: &mut Geom, precision: f64) {
+    if precision > 0.0 {
+        x.geom.map_coords_in_place(|Coord { x, y }| Coord {
+            x: (x * precision).round() / precision,
+            y: (y * precision).round() / precision,
+        });
+    } else if precision < 0.0 {
+        x.geom.map_coords_in_place(|Coord { x, y }| Coord {
+            x: x as f32 as f64,
+            y: y as f32 as f64,
+        });
+    }
+}
+
+/// Like `sfc_to_geoms()` but applies `hook` to the coordinates of each geometry as
+/// it is converted. See `apply_coord_hook()`.
+pub fn sfc_to_geoms_with_hook(x: List, hook: &Function) -> Result<Vec<Option<Geom>>, Box<dyn Error>> {
+    let mut res = Vec::with_capacity(x.len());
+
+    for (i, geom) in SfcIter::new(x).enumerate() {
+        let geom = match geom.ok().flatten() {
+            Some(mut geom) => {
+                apply_coord_hook(&mut geom, hook, i)?;
+                Some(geom)
+            }
+            None => None,
+        };
+
+        res.push(geom);
+    }
+
+    Ok(res)
+}
+
+/// Transform the coordinates of a `Geom` with an R function. `hook` is called with an
+/// n x 2 matrix of all of the coordinates of the geometry and must return a numeric
+/// n x 2 matrix which replaces them. `i` is the index of the geometry which is
+/// reported if `hook` errors or returns a matrix of the wrong dimensions.
+///
+/// `Line`, `Rect`, and `Triangle` geometries are converted to a `LineString` or
+/// `Polygon` first as they would be by `tosf::geoms_to_sfc()`.
+pub fn apply_coord_hook(x: &mut Geom, hook: &Function, i: usize) -> extendr_api::Result<()> {
+    let geom = std::mem::replace(&mut x.geom, Geometry::GeometryCollection(Default::default()));
+    x.geom = expand_primitives(geom);
+
+    let n = x.geom.coords_count();
+
+    if n == 0 {
+        return Ok(());
+    }
+
+    let mut data = vec![0.0; n * 2];
+    for (j, crd) in x.geom.coords_iter().enumerate() {
+        data[j] = crd.x;
+        data[j + n] = crd.y;
+    }
+
+    let res = hook
+        .call(pairlist!(crate::tosf::new_matrix(data, n, 2)))
+        .map_err(|e| extendr_api::Error::Other(format!("Coordinate hook failed for feature {}\n{e}", i + 1)))?;
+
+    let dim = res.dim().map(|dim| dim.iter().map(|d| d.inner()).collect::<Vec<i32>>());
+
+    let values = match (dim.as_deref(), res.as_real_slice()) {
+        (Some([nrow, 2]), Some(values)) if *nrow as usize == n => values,
+        _ => {
+            return Err(extendr_api::Error::Other(format!(
+                "Coordinate hook must return a numeric {n} x 2 matrix for feature {}",
+                i + 1
+            )))
+        }
+    };
+
+    let (xs, ys) = values.split_at(n);
+    let j = std::cell::Cell::new(0);
+
+    x.geom.map_coords_in_place(|_| {
+        let k = j.get();
+        j.set(k + 1);
+        Coord { x: xs[k], y: ys[k] }
+    });
+
+    Ok(())
+}
+
+// `coords_iter()` yields every corner of a `Rect` and `Triangle` while
+// `map_coords_in_place()` only visits the ones that are stored. Converting them
+// first makes both visit the same coordinates in the same order.
+fn expand_primitives(x: Geometry) -> Geometry {
+    match x {
+        Geometry::Line(x) => LineString::new(vec![x.start, x.end]).into(),
+        Geometry::Rect(x) => x.to_polygon().into(),
+        Geometry::Triangle(x) => x.to_polygon().into(),
+        Geometry::GeometryCollection(x) => x
+            .into_iter()
+            .map(expand_primitives)
+            .collect::<GeometryCollection>()
+            .into(),
+        x => x,
+    }
+}
+
+// each geometry is snapped to `precision` as it is converted
+#[cfg(not(feature = "parallel"))]
+fn convert_sfc(x: List, precision: f64) -> Vec<Option<Geom>> {
+    if let Some((geoms, _)) = sfc_fast_path(&x, precision) {
+        return geoms;
+    }
+
+    SfcIter::with_precision(x, precision)
+        .map(|geom| geom.ok().flatten())
+        .collect::<Vec<Option<Geom>>>()
+}
+
+/// Lazily converts an sfc object into `Geom` structs. Elements are only converted
+/// as the iterator is consumed so that geometries can be folded over without
+/// materializing a `Vec<Option<Geom>>`. See `SfcIter`.
+///
+/// ```
+/// for geom in sfc_geoms_iter(&x) {
+///     let geom = geom.unwrap();
+/// }
+/// ```
+pub fn sfc_geoms_iter(x: &List) -> SfcIter {
+    SfcIter::new(x.clone())
+}
+
+/// A lazy iterator over the geometries of an sfc object. Each sfg is converted
+/// when it is reached so geometries can be processed one at a time without
+/// collecting them. NULL geometries are `Ok(None)` and sfg that cannot be
+/// converted are an error. Use `.map(|geom| geom.ok().flatten())` to treat both
+/// as missing as `sfc_to_geoms()` does.
+pub struct SfcIter {
+    x: List,
+    i: usize,
+    precision: f64,
+}
+
+impl SfcIter {
+    /// Create an iterator over the elements of an sfc object. Coordinates are
+    /// snapped to the sfc's precision as in `sfc_to_geoms()`.
+    pub fn new(x: List) -> Self {
+        let precision = sfc_precision(&x);
+        Self::with_precision(x, precision)
+    }
+
+    /// Like `SfcIter::new()` but snaps coordinates to `precision` instead of the
+    /// sfc's `precision` attribute. Use `0` to keep the raw coordinates.
+    pub fn with_precision(x: List, precision: f64) -> Self {
+        SfcIter { x, i: 0, precision }
+    }
+}
+
+impl Iterator for SfcIter {
+    type Item = Result<Option<Geom>, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i >= self.x.len() {
+            return None;
+        }
+
+        let robj = self.x.elt(self.i);
+        self.i += 1;
+
+        let res = match robj {
+            Ok(robj) if robj.is_null() => Ok(None),
+            Ok(robj) => sfg_to_geom(robj).map(|mut geom| {
+                snap_to_precision(&mut geom, self.precision);
+                Some(geom)
+            }),
+            Err(e) => Err(e.into()),
+        };
+
+        Some(res)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.x.len() - self.i;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for SfcIter {}
+
+/// With the `parallel` feature the coordinates of each sfg are first copied into
+/// Rust owned buffers on the R thread. The `Geom`s are then built from these
+/// buffers in parallel using rayon. The order of the output matches the input.
+#[cfg(feature = "parallel")]
+fn convert_sfc(x: List, precision: f64) -> Vec<Option<Geom>> {
+    use rayon::prelude::*;
+
+    let buffers = x
+        .iter()
+        .map(|(_, robj)| SfgBuffer::new(&robj))
+        .collect::<Vec<SfgBuffer>>();
+
+    buffers
+        .into_par_iter()
+        .map(|buffer| {
+            let mut geom = buffer.into_geom()?;
+            snap_to_precision(&mut geom, precision);
+            Some(geom)
+        })
+        .collect::<Vec<Option<Geom>>>()
+}
+
+// An owned copy of the coordinates of an sfg. R objects must only be read from
+// the R thread so this is used to hand the coordinates to other threads. The
+// coordinates are read with the same reader as the sequential path.
+#[cfg(feature = "parallel")]
+enum SfgBuffer {
+    Missing,
+    Geom(Geom),
+    Point(Coord),
+    MultiPoint(Vec<Coord>),
+    LineString(Vec<Coord>),
+    MultiLineString(Vec<Vec<Coord>>),
+    Polygon(Vec<Vec<Coord>>),
+    MultiPolygon(Vec<Vec<Vec<Coord>>>),
+}
+
+#[cfg(feature = "parallel")]
+impl SfgBuffer {
+    // sfg that cannot be read are missing as they are in `SfcIter`
+    fn new(x: &Robj) -> Self {
+        Self::read(x).unwrap_or(SfgBuffer::Missing)
+    }
+
+    fn read(x: &Robj) -> Option<Self> {
+        if x.is_null() {
+            return Some(SfgBuffer::Missing);
+        }
+
+        if x.inherits("Geom") {
+            return Some(SfgBuffer::Geom(<&Geom>::from_robj(x).ok()?.clone()));
+        }
+
+        let cls = x.class()?.nth(1)?;
+
+        let res = match cls {
+            "POINT" => SfgBuffer::Point(point_coord(x).ok()?),
+            "MULTIPOINT" => {
+                let pnts = multipoint_to_points(x, &LENIENT).ok()?;
+                SfgBuffer::MultiPoint(pnts.into_iter().map(|pnt| pnt.0).collect())
+            }
+            "LINESTRING" => SfgBuffer::LineString(robj_to_coords(x)?),
+            "MULTILINESTRING" => SfgBuffer::MultiLineString(rings_buffer(x)?),
+            "POLYGON" => SfgBuffer::Polygon(rings_buffer(x)?),
+            "MULTIPOLYGON" => {
+                let res = x
+                    .as_list()?
+                    .iter()
+                    .map(|(_, poly)| rings_buffer(&poly))
+                    .collect::<Option<Vec<Vec<Vec<Coord>>>>>()?;
+                SfgBuffer::MultiPolygon(res)
+            }
+            &_ => return None,
+        };
+
+        Some(res)
+    }
+
+    fn into_geom(self) -> Option<Geom> {
+        let geom = match self {
+            SfgBuffer::Missing => return None,
+            SfgBuffer::Geom(x) => x,
+            SfgBuffer::Point(x) => Geom::from(Point::from(x)),
+            SfgBuffer::MultiPoint(x) => Geom::from(MultiPoint::from(x)),
+            SfgBuffer::LineString(x) => Geom::from(LineString::new(x)),
+            SfgBuffer::MultiLineString(x) => {
+                let lns = x
+                    .into_iter()
+                    .map(LineString::new)
+                    .collect::<Vec<LineString>>();
+                Geom::from(MultiLineString::new(lns))
+            }
+            SfgBuffer::Polygon(x) => Geom::from(polygon_from_buffers(x)),
+            SfgBuffer::MultiPolygon(x) => {
+                let polys = x
+                    .into_iter()
+                    .map(polygon_from_buffers)
+                    .collect::<Vec<Polygon>>();
+                Geom::from(MultiPolygon::new(polys))
+            }
+        };
+
+        Some(geom)
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn rings_buffer(x: &Robj) -> Option<Vec<Vec<Coord>>> {
+    x.as_list()?
+        .iter()
+        .map(|(_, ring)| robj_to_coords(&ring))
+        .collect()
+}
+
+#[cfg(feature = "parallel")]
+fn polygon_from_buffers(x: Vec<Vec<Coord>>) -> Polygon {
+    let mut rings = x.into_iter().map(LineString::new);
+    let exterior = rings.next().unwrap_or_else(|| LineString::new(vec![]));
+
+    Polygon::new(exterior, rings.collect())
+}
+
+
+
+/// Fast path for `sfc_MULTIPOINT`, `sfc_LINESTRING`, `sfc_POLYGON`, and
+/// `sfc_MULTIPOLYGON` objects. Since the sfc is homogeneous the sfg class does not
+/// need to be dispatched on for every element and the coordinate matrices are
+/// copied directly into a `Vec<Coord>` from their column slices. Returns `None` if
+/// `x` is not one of these sfc types, otherwise the geometries and their rsgeo class.
+fn sfc_fast_path(x: &List, precision: f64) -> Option<(Vec<Option<Geom>>, &'static str)> {
+    let cls = if x.inherits("sfc_MULTIPOINT") {
+        "multipoint"
+    } else if x.inherits("sfc_LINESTRING") {
+        "linestring"
+    } else if x.inherits("sfc_POLYGON") {
+        "polygon"
+    } else if x.inherits("sfc_MULTIPOLYGON") {
+        "multipolygon"
+    } else {
+        return None;
+    };
+
+    let res = x
+        .iter()
         .map(|(_, robj)| {
-            let geo = sfg_to_geom(robj);
-            match geo {
-                Ok(g) => Some(g),
-                Err(_) => None
+            let mut geom = sfg_fast(&robj, cls)?;
+            snap_to_precision(&mut geom, precision);
+            Some(geom)
+        })
+        .collect::<Vec<Option<Geom>>>();
+
+    Some((res, cls))
+}
+
+// converts a single sfg of a known type. NULL geometries are `None`
+fn sfg_fast(x: &Robj, cls: &str) -> Option<Geom> {
+    if x.is_null() {
+        return None;
+    }
+
+    let geom = match cls {
+        "multipoint" if x.is_matrix() => Geom::from(MultiPoint::from(robj_to_coords(x)?)),
+        "multipoint" => Geom::from(MultiPoint::new(multipoint_to_points(x, &LENIENT).ok()?)),
+        "linestring" => Geom::from(LineString::new(robj_to_coords(x)?)),
+        "polygon" => Geom::from(polygon_fast(x.as_list()?)?),
+        "multipolygon" => {
+            let x = x.as_list()?;
+            let mut polys: Vec<Polygon> = Vec::with_capacity(x.len());
+
+            for (_, poly) in x.iter() {
+                polys.push(polygon_fast(poly.as_list()?)?);
             }
-        }).collect::<Vec<Option<Geom>>>()
+
+            Geom::from(MultiPolygon::new(polys))
+        }
+        _ => unreachable!(),
+    };
+
+    Some(geom)
+}
+
+// walks the list of rings once without re-dispatching on the sfg class
+fn polygon_fast(x: List) -> Option<Polygon> {
+    let mut rings = x
+        .iter()
+        .map(|(_, ring)| robj_to_coords(&ring).map(LineString::new))
+        .collect::<Option<Vec<LineString>>>()?
+        .into_iter();
+
+    let exterior = rings.next().unwrap_or_else(|| LineString::new(vec![]));
+    let mut interiors: Vec<LineString> = Vec::with_capacity(x.len().saturating_sub(1));
+    interiors.extend(rings);
+
+    Some(Polygon::new(exterior, interiors))
+}
+
+// the coordinates of a POINT sfg. An empty POINT is `c(NA, NA)`
+fn point_coord(x: &Robj) -> Result<Coord, Box<dyn Error>> {
+    match x.as_real_slice() {
+        Some(&[x, y, ..]) => Ok(Coord { x, y }),
+        _ => Err("POINT must be a numeric vector of length 2".into()),
+    }
+}
+
+// uses the same reader as the constructors so columns named x and y are
+// selected by name. Malformed matrices are `None` as in `sfg_to_geom()`
+fn robj_to_coords(x: &Robj) -> Option<Vec<Coord>> {
+    coords_from_robj(x, &LENIENT).ok()
+}
+
+/// Given an sf data.frame, find the geometry column using its `sf_column` attribute
+/// and convert it with `sfc_to_geoms()`. Errors if `x` is not an sf object or if the
+/// `sf_column` attribute is missing or does not refer to an sfc column.
+pub fn sf_to_geoms(x: &List) -> Result<Vec<Option<Geom>>, Box<dyn Error>> {
+    let (_, sfc) = sf_geometry_column(x)?;
+    Ok(sfc_to_geoms(sfc))
 }
 
+/// Returns the columns of an sf data.frame other than the geometry column.
+/// Use with `sf_to_geoms()` to keep the attributes aligned with the geometries.
+pub fn sf_attributes(x: &List) -> Result<List, Box<dyn Error>> {
+    let (sf_column, _) = sf_geometry_column(x)?;
+
+    let (names, values): (Vec<&str>, Vec<Robj>) = x
+        .iter()
+        .filter(|(name, _)| *name != sf_column)
+        .unzip();
 
+    Ok(List::from_names_and_values(names, values)?)
+}
+
+// returns the name of the geometry column and the sfc itself
+pub(crate) fn sf_geometry_column(x: &List) -> Result<(String, List), Box<dyn Error>> {
+    if !x.inherits("sf") {
+        return Err("`x` must be an object of class `sf`".into());
+    }
+
+    let sf_column = x
+        .get_attrib("sf_column")
+        .and_then(|col| col.as_str().map(String::from))
+        .ok_or("`x` is missing the `sf_column` attribute")?;
+
+    let sfc = x
+        .iter()
+        .find(|(name, _)| *name == sf_column)
+        .map(|(_, col)| col)
+        .ok_or(format!("`sf_column` refers to `{sf_column}` which is not a column of `x`"))?;
+
+    if !sfc.inherits("sfc") {
+        return Err(format!("`sf_column` refers to `{sf_column}` which is not an sfc column").into());
+    }
+
+    let sfc = List::try_from(sfc)?;
+    Ok((sf_column, sfc))
+}
+
+/// Convert an sf `bbox` object such as the result of `sf::st_bbox()` into a `Rect`.
+/// Errors if `x` is not a `bbox`, is missing any of `xmin`, `ymin`, `xmax`, or `ymax`,
+/// or is not a valid extent. See `constructors::extent_to_rect()`.
+pub fn bbox_to_rect(x: &Robj) -> Result<Rect, Box<dyn Error>> {
+    if !x.inherits("bbox") {
+        return Err("`x` must be an object of class `bbox`".into());
+    }
+
+    let values = x.as_real_slice().ok_or("`x` must be a numeric vector")?;
+    let names = x.names().ok_or("`x` must be named")?.collect::<Vec<&str>>();
+
+    let get = |name: &str| -> Result<f64, Box<dyn Error>> {
+        let value = names
+            .iter()
+            .position(|n| *n == name)
+            .and_then(|i| values.get(i))
+            .ok_or(format!("`x` is missing `{name}`"))?;
+
+        Ok(*value)
+    };
+
+    let extent = [get("xmin")?, get("ymin")?, get("xmax")?, get("ymax")?];
+    Ok(extent_to_rect(extent)?)
+}
+
+/// Determine which elements of an sfc object are empty without converting them to
+/// geo-types. An sfg is empty if it is an `NA` POINT, a matrix with zero rows, or a
+/// list of length zero. rsgeo vctrs are also supported by checking each `Geom`.
+/// NULL elements are `NA`.
+pub fn sfc_is_empty(x: List) -> Logicals {
+    Logicals::from_values(x.iter().map(|(_, robj)| sfg_is_empty(&robj)))
+}
+
+fn sfg_is_empty(x: &Robj) -> Rbool {
+    if x.is_null() {
+        return Rbool::na();
+    }
+
+    if x.inherits("Geom") {
+        return match <&Geom>::from_robj(x) {
+            Ok(geom) => geom.geom.is_empty().into(),
+            Err(_) => Rbool::na(),
+        };
+    }
+
+    let cls = match x.class() {
+        Some(cls) => cls.collect::<Vec<&str>>(),
+        None => return Rbool::na(),
+    };
+
+    match cls.get(1) {
+        Some(&"POINT") => x
+            .as_real_slice()
+            .map(|crds| crds.iter().all(|crd| crd.is_nan()).into())
+            .unwrap_or(Rbool::na()),
+        // zero row matrices and zero length lists
+        Some(_) => (x.len() == 0).into(),
+        None => Rbool::na(),
+    }
+}
+
+// Typed extraction
+// These are for when the sfc is known to be homogeneous. Each element is checked
+// and an error is returned with the position and actual type of the first element
+// that does not match.
+
+/// Extract a vector of `Point`s from an `sfc_POINT` object.
+pub fn sfc_points(x: &List) -> Result<Vec<Option<Point>>, Box<dyn Error>> {
+    sfc_typed(x, "POINT")
+}
+
+/// Extract a vector of `MultiPoint`s from an `sfc_MULTIPOINT` object.
+pub fn sfc_multipoints(x: &List) -> Result<Vec<Option<MultiPoint>>, Box<dyn Error>> {
+    sfc_typed(x, "MULTIPOINT")
+}
+
+/// Extract a vector of `LineString`s from an `sfc_LINESTRING` object.
+pub fn sfc_linestrings(x: &List) -> Result<Vec<Option<LineString>>, Box<dyn Error>> {
+    sfc_typed(x, "LINESTRING")
+}
+
+/// Extract a vector of `MultiLineString`s from an `sfc_MULTILINESTRING` object.
+pub fn sfc_multilinestrings(x: &List) -> Result<Vec<Option<MultiLineString>>, Box<dyn Error>> {
+    sfc_typed(x, "MULTILINESTRING")
+}
+
+/// Extract a vector of `Polygon`s from an `sfc_POLYGON` object.
+pub fn sfc_polygons(x: &List) -> Result<Vec<Option<Polygon>>, Box<dyn Error>> {
+    sfc_typed(x, "POLYGON")
+}
+
+/// Extract a vector of `MultiPolygon`s from an `sfc_MULTIPOLYGON` object.
+pub fn sfc_multipolygons(x: &List) -> Result<Vec<Option<MultiPolygon>>, Box<dyn Error>> {
+    sfc_typed(x, "MULTIPOLYGON")
+}
+
+fn sfc_typed<T>(x: &List, expected: &str) -> Result<Vec<Option<T>>, Box<dyn Error>>
+where
+    T: TryFrom<Geometry>,
+{
+    sfc_geoms_iter(x)
+        .enumerate()
+        .map(|(i, geom)| {
+            let geom = match geom? {
+                Some(geom) => geom.geom,
+                None => return Ok(None),
+            };
+
+            let actual = geometry_type(&geom);
+
+            match T::try_from(geom) {
+                Ok(geom) => Ok(Some(geom)),
+                Err(_) => Err(format!("Expected {expected} but element {} is {actual}", i + 1).into()),
+            }
+        })
+        .collect()
+}
 
 
 /// Falliably takes an extendr `Robj` and returns a `Geom` struct.
 /// Supports conversion from `"POINT"`, `"MULTIPOINT"`, `"LINESTRING"`, `"MULTILINESTRING"`,
 /// `"POLYGON"`, and `"MULTIPOLYGON"` to their corresponding geo_type primitive. 
+/// If `x` is already an rsgeo `Geom` it is cloned.
 // `GEOMETRYCOLLECTION` are not supported.
 /// 
 /// ```
@@ -104,35 +725,33 @@ pub fn sfc_to_geoms(x: List) -> Vec<Option<Geom>> {
 /// 
 pub fn sfg_to_geom(x: Robj) -> Result<Geom, Box<dyn Error>> {
 
-    let cls2 = x.class().unwrap().map(|x| x).collect::<Vec<&str>>();
-    let cls = cls2[1];
+    // rsgeo geometries are already a Geom
+    if x.inherits("Geom") || x.rtype() == Rtype::ExternalPtr {
+        return Ok(<&Geom>::from_robj(&x)?.clone());
+    }
+
+    let cls = x
+        .class()
+        .and_then(|mut cls| cls.nth(1))
+        .ok_or("`x` must be an sfg object")?;
 
     match cls {
-        "POINT" => {
-            let x = Doubles::try_from(x).unwrap();
-            Ok(geom_point(x[0].inner(), x[1].inner()).into())
-        }
-        "MULTIPOINT" => {
-            let x = RMatrix::from_robj(&x).unwrap();
-            Ok(geom_multipoint(x).into())
-        }
-        "LINESTRING" => {
-            let x = RMatrix::from_robj(&x).unwrap();
-            Ok(geom_linestring(x).into())
-        }
+        "POINT" => Ok(Geom::from(Point::from(point_coord(&x)?))),
+        "MULTIPOINT" => Ok(Geom::from(MultiPoint::new(multipoint_to_points(&x, &LENIENT)?))),
+        "LINESTRING" => Ok(Geom::from(LineString::new(coords_from_robj(&x, &LENIENT)?))),
 
         "MULTILINESTRING" => {
-            let x = List::try_from(x).unwrap();
-            Ok(geom_multilinestring(x).into())
+            let x = List::try_from(x)?;
+            Ok(geom_multilinestring_checked(x, &LENIENT)?.into())
         }
         "POLYGON" => {
-            let x = List::try_from(x).unwrap();
-            Ok(geom_polygon(x).into())
+            let x = List::try_from(x)?;
+            Ok(Geom::from(list_to_polygon_with(x, &LENIENT)?))
         }
 
         "MULTIPOLYGON" => {
-            let x = List::try_from(x).unwrap();
-            Ok(geom_multipolygon(x).into())
+            let x = List::try_from(x)?;
+            Ok(Geom::from(MultiPolygon::new(list_to_polygons_with(x, &LENIENT)?)))
         }
 
         &_ => Err(format!("Null or unsupported geometry type").into()),
@@ -141,39 +760,348 @@ pub fn sfg_to_geom(x: Robj) -> Result<Geom, Box<dyn Error>> {
 
 use crate::constructors::*;
 
+// sf has already validated its geometries so they are read as is
+const LENIENT: GeomOptions = GeomOptions::lenient();
+
+/// Converts an sfg into a `Geom` pointer with its scalar class. Uses
+/// `sfg_to_geom()` so that the result is the same as converting the sfc. Null or
+/// unsupported geometries are `NULL`.
 #[extendr]
 pub fn sfg_to_rsgeo(x: Robj) -> Robj {
-    let cls2 = x.class().unwrap().map(|x| x).collect::<Vec<&str>>();
-    let cls = cls2[1];
+    if x.inherits("Geom") {
+        return x;
+    }
 
-    match cls {
-        "POINT" => {
-            let x = Doubles::try_from(x).unwrap();
-            geom_point(x[0].inner(), x[1].inner())
+    match sfg_to_geom(x) {
+        Ok(geom) => geom_to_robj(geom),
+        Err(_) => Robj::from(NULL),
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sfg_keeps_missing_coordinates() {
+        test! {
+            let sfc = List::try_from(R!("structure(
+                list(
+                    structure(matrix(c(0, 1, 0, NA), ncol = 2), class = c('XY', 'LINESTRING', 'sfg')),
+                    structure(list(matrix(c(0, 1, NaN, 1), ncol = 2)), class = c('XY', 'MULTILINESTRING', 'sfg'))
+                ),
+                class = c('sfc_GEOMETRY', 'sfc')
+            )").unwrap()).unwrap();
+
+            let geoms = sfc_to_geoms(sfc);
+            assert!(geoms.iter().all(|geom| geom.is_some()));
+
+            let lns: LineString = geoms[0].clone().unwrap().geom.try_into().unwrap();
+            assert!(lns.0[1].y.is_nan());
+        }
+    }
+
+    #[test]
+    fn named_columns_are_read_by_name() {
+        test! {
+            let sfc = |cls: &str| {
+                let x = R!("list(structure(
+                    matrix(c(10, 11, 0, 1), ncol = 2, dimnames = list(NULL, c('y', 'x'))),
+                    class = c('XY', 'LINESTRING', 'sfg')
+                ))").unwrap();
+                List::try_from(x.set_class([cls, "sfc"]).unwrap()).unwrap()
+            };
+
+            let expected = LineString::from(vec![(0.0, 10.0), (1.0, 11.0)]);
+
+            for cls in ["sfc_LINESTRING", "sfc_GEOMETRY"] {
+                let lns: LineString = sfc_to_geoms(sfc(cls))[0].clone().unwrap().geom.try_into().unwrap();
+                assert_eq!(lns, expected);
+            }
         }
-        "MULTIPOINT" => {
-            let x = RMatrix::from_robj(&x).unwrap();
-            geom_multipoint(x)
+    }
+
+    // the same sfg as an sfc_POLYGON, using the fast path, and an sfc_GEOMETRY
+    fn polygon_sfcs() -> (List, List) {
+        let x = R!("list(
+            structure(list(matrix(c(0, 10, 10, 0, 0, 0, 10, 10), ncol = 2)), class = c('XY', 'POLYGON', 'sfg')),
+            structure(list(
+                matrix(c(0, 10, 10, 0, 0, 0, 0, 10, 10, 0), ncol = 2),
+                matrix(c(2, 4, 2, 2), ncol = 2)
+            ), class = c('XY', 'POLYGON', 'sfg')),
+            structure(list(matrix(c(0, 1, NA, 0, 0, 0, 1, 1, 0, 0), ncol = 2)), class = c('XY', 'POLYGON', 'sfg')),
+            structure(list(), class = c('XY', 'POLYGON', 'sfg')),
+            NULL
+        )").unwrap();
+
+        let sfc = |cls: &str| List::try_from(x.clone().set_class([cls, "sfc"]).unwrap()).unwrap();
+        (sfc("sfc_POLYGON"), sfc("sfc_GEOMETRY"))
+    }
+
+    // the same list of sfg as a homogeneous sfc, using the fast path, and an sfc_GEOMETRY
+    fn sfcs(x: &str, cls: &str) -> (List, List) {
+        let x = eval_string(x).unwrap();
+        let sfc = |cls: &str| List::try_from(x.clone().set_class([cls, "sfc"]).unwrap()).unwrap();
+        (sfc(cls), sfc("sfc_GEOMETRY"))
+    }
+
+    // converts with every entry point, which must all agree
+    fn converted(x: List) -> Vec<Option<Geometry>> {
+        let geoms = geometries(sfc_to_geoms(x.clone()));
+        let rsgeo = crate::geoms_from_list(List::try_from(sfc_to_rsgeo(x.clone())).unwrap());
+
+        assert_eq!(geometries(rsgeo), geoms);
+        assert_eq!(sfc_to_geometry(x), geoms);
+        geoms
+    }
+
+    fn geometries(x: Vec<Option<Geom>>) -> Vec<Option<Geometry>> {
+        x.into_iter().map(|geom| geom.map(|geom| geom.geom)).collect()
+    }
+
+    #[test]
+    fn polygons_match_on_every_path() {
+        test! {
+            let (fast, generic) = polygon_sfcs();
+
+            let fast = geometries(sfc_to_geoms(fast));
+            let generic = geometries(sfc_to_geoms(generic));
+
+            // an unclosed exterior, a 2 point hole, an NA coordinate, and an empty
+            // polygon are all accepted as sf accepts them
+            assert_eq!(fast, generic);
+            assert!(fast[..4].iter().all(|geom| geom.is_some()));
+            assert!(fast[4].is_none());
+
+            let poly: Polygon = fast[0].clone().unwrap().try_into().unwrap();
+            assert!(poly.exterior().is_closed());
         }
-        "LINESTRING" => {
-            let x = RMatrix::from_robj(&x).unwrap();
-            geom_linestring(x)
+    }
+
+    #[test]
+    fn malformed_multipoints_are_missing() {
+        test! {
+            let sfc = List::try_from(R!("structure(list(
+                structure(list(c(0, 1), c(2, 3)), class = c('XY', 'MULTIPOINT', 'sfg')),
+                structure(list(c(0, 1), 'a'), class = c('XY', 'MULTIPOINT', 'sfg'))
+            ), class = c('sfc_MULTIPOINT', 'sfc'))").unwrap()).unwrap();
+
+            // with the `parallel` feature this goes through `SfgBuffer`
+            let geoms = geometries(sfc_to_geoms(sfc));
+            assert_eq!(geoms[0], Some(MultiPoint::from(vec![(0.0, 1.0), (2.0, 3.0)]).into()));
+            assert_eq!(geoms[1], None);
         }
+    }
 
-        "MULTILINESTRING" => {
-            let x = List::try_from(x).unwrap();
-            geom_multilinestring(x)
+    #[test]
+    fn iterator_is_lazy() {
+        test! {
+            // a bad element after the first 10 is never converted
+            let sfc = List::try_from(R!("structure(
+                c(
+                    lapply(1:10, function(i) structure(c(i, i), class = c('XY', 'POINT', 'sfg'))),
+                    list(structure(list(), class = c('XY', 'UNKNOWN', 'sfg')))
+                ),
+                class = c('sfc_GEOMETRY', 'sfc')
+            )").unwrap()).unwrap();
+
+            let first = sfc_geoms_iter(&sfc)
+                .take(10)
+                .collect::<Result<Vec<Option<Geom>>, Box<dyn Error>>>()
+                .unwrap();
+
+            assert_eq!(first.len(), 10);
+            assert_eq!(first[9].clone().unwrap().geom, Point::new(10.0, 10.0).into());
+
+            let mut iter = SfcIter::new(sfc);
+            assert_eq!(iter.len(), 11);
+            assert!(iter.nth(10).unwrap().is_err());
+            assert!(iter.next().is_none());
         }
-        "POLYGON" => {
-            let x = List::try_from(x).unwrap();
-            geom_polygon(x)
+    }
+
+    #[test]
+    fn precision_on_every_entry_point() {
+        test! {
+            let expected: Geometry = LineString::from(vec![(0.1, 0.1), (1.1, 1.0)]).into();
+            let raw: Geometry = LineString::from(vec![(0.123, 0.1), (1.149, 0.96)]).into();
+
+            for cls in ["sfc_LINESTRING", "sfc_GEOMETRY"] {
+                let sfc = List::try_from(eval_string(&format!("structure(
+                    list(structure(
+                        matrix(c(0.123, 1.149, 0.1, 0.96), ncol = 2),
+                        class = c('XY', 'LINESTRING', 'sfg')
+                    )),
+                    precision = 10,
+                    class = c('{cls}', 'sfc')
+                )")).unwrap()).unwrap();
+
+                assert_eq!(sfc_to_geoms(sfc.clone())[0].clone().unwrap().geom, expected);
+                assert_eq!(sfc_to_geometry(sfc.clone())[0], Some(expected.clone()));
+
+                let rsgeo = List::try_from(sfc_to_rsgeo(sfc.clone())).unwrap();
+                let geoms = crate::geoms_from_list(rsgeo);
+                assert_eq!(geoms[0].clone().unwrap().geom, expected);
+
+                let first = sfc_geoms_iter(&sfc).next().unwrap().unwrap().unwrap();
+                assert_eq!(first.geom, expected);
+
+                let (geoms, precision) = sfc_to_geoms_precision(sfc, true);
+                assert_eq!(precision, 10.0);
+                assert_eq!(geoms[0].clone().unwrap().geom, raw);
+            }
         }
+    }
 
-        "MULTIPOLYGON" => {
-            let x = List::try_from(x).unwrap();
-            geom_multipolygon(x)
+    #[test]
+    fn coord_hook_is_applied_while_converting() {
+        test! {
+            let hook = Function::try_from(R!("function(m) m * 1000").unwrap()).unwrap();
+            let sfc = List::try_from(R!("structure(list(
+                structure(matrix(c(0, 1, 2, 3), ncol = 2), class = c('XY', 'LINESTRING', 'sfg')),
+                NULL,
+                structure(list(matrix(c(0, 1, 1, 0, 0, 0, 1, 0), ncol = 2)), class = c('XY', 'POLYGON', 'sfg'))
+            ), class = c('sfc_GEOMETRY', 'sfc'))").unwrap()).unwrap();
+
+            let geoms = geometries(sfc_to_geoms_with_hook(sfc, &hook).unwrap());
+
+            assert_eq!(geoms[0], Some(LineString::from(vec![(0.0, 2000.0), (1000.0, 3000.0)]).into()));
+            assert_eq!(geoms[1], None);
+            assert_eq!(
+                geoms[2],
+                Some(Polygon::new(
+                    LineString::from(vec![(0.0, 0.0), (1000.0, 0.0), (1000.0, 1000.0), (0.0, 0.0)]),
+                    vec![],
+                ).into())
+            );
+
+            // a `Rect` becomes a polygon so every corner is transformed
+            let mut rect = Geom::from(Rect::new((0.0, 0.0), (1.0, 2.0)));
+            apply_coord_hook(&mut rect, &hook, 0).unwrap();
+            assert_eq!(rect.geom, Rect::new((0.0, 0.0), (1000.0, 2000.0)).to_polygon().into());
+
+            let bad = Function::try_from(R!("function(m) m[-1, ]").unwrap()).unwrap();
+            let err = apply_coord_hook(&mut rect, &bad, 2).unwrap_err().to_string();
+            assert!(err.contains("feature 3"));
+        }
+    }
+
+    #[test]
+    fn multipoints_and_linestrings_match_generic_path() {
+        test! {
+            for sfg in ["MULTIPOINT", "LINESTRING"] {
+                let (fast, generic) = sfcs(&format!("list(
+                    structure(matrix(c(0, 1, 2, 10, 11, 12), ncol = 2), class = c('XY', '{sfg}', 'sfg')),
+                    structure(matrix(numeric(0), ncol = 2), class = c('XY', '{sfg}', 'sfg')),
+                    NULL,
+                    structure(matrix(c(5, NA, 5, 6), ncol = 2), class = c('XY', '{sfg}', 'sfg'))
+                )"), &format!("sfc_{sfg}"));
+
+                let fast = converted(fast);
+                let generic = converted(generic);
+
+                // NaN != NaN so compare the debug representation
+                assert_eq!(format!("{fast:?}"), format!("{generic:?}"));
+                assert_eq!(fast[0].clone().unwrap().coords_count(), 3);
+
+                // zero row matrices are empty geometries, not missing
+                assert!(fast[1].clone().unwrap().is_empty());
+                assert!(fast[2].is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn polygons_with_holes_match_generic_path() {
+        test! {
+            // a square with a hole and a polygon with 50 rings
+            let polys = "list(
+                structure(list(
+                    matrix(c(0, 10, 10, 0, 0, 0, 0, 10, 10, 0), ncol = 2),
+                    matrix(c(2, 4, 4, 2, 2, 2, 2, 4, 4, 2), ncol = 2)
+                ), class = c('XY', 'POLYGON', 'sfg')),
+                structure(lapply(1:50, function(i) {
+                    matrix(c(0, i, i, 0, 0, 0, 0, i, i, 0), ncol = 2)
+                }), class = c('XY', 'POLYGON', 'sfg')),
+                NULL
+            )";
+
+            let (fast, generic) = sfcs(polys, "sfc_POLYGON");
+            let fast = converted(fast);
+            assert_eq!(fast, converted(generic));
+
+            let poly: Polygon = fast[0].clone().unwrap().try_into().unwrap();
+            assert_eq!(poly.interiors().len(), 1);
+
+            let poly: Polygon = fast[1].clone().unwrap().try_into().unwrap();
+            assert_eq!(poly.interiors().len(), 49);
+            assert_eq!(poly.interiors()[48].0[1], Coord { x: 50.0, y: 0.0 });
+
+            let (fast, generic) = sfcs(&format!("list(
+                structure(lapply({polys}[1:2], unclass), class = c('XY', 'MULTIPOLYGON', 'sfg')),
+                structure(list(), class = c('XY', 'MULTIPOLYGON', 'sfg')),
+                NULL
+            )"), "sfc_MULTIPOLYGON");
+
+            let fast = converted(fast);
+            assert_eq!(fast, converted(generic));
+
+            let mpoly: MultiPolygon = fast[0].clone().unwrap().try_into().unwrap();
+            assert_eq!(mpoly.0.len(), 2);
+            assert_eq!(mpoly.0[1].interiors().len(), 49);
+            assert!(fast[1].clone().unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn malformed_polygons_are_missing() {
+        test! {
+            for (cls, ring) in [("POLYGON", "m"), ("MULTIPOLYGON", "list(m)")] {
+                // a component that is a string and one that is not a matrix or list
+                let (fast, generic) = sfcs(&format!("m <- matrix(c(0, 1, 1, 0, 0, 1, 0, 0), ncol = 2)
+                list(
+                    structure(list({ring}), class = c('XY', '{cls}', 'sfg')),
+                    structure(list('a'), class = c('XY', '{cls}', 'sfg')),
+                    structure(list(1), class = c('XY', '{cls}', 'sfg'))
+                )"), &format!("sfc_{cls}"));
+
+                let fast = converted(fast);
+                assert_eq!(fast, converted(generic));
+                assert!(fast[0].is_some());
+                assert!(fast[1..].iter().all(|geom| geom.is_none()));
+            }
         }
+    }
+
+    #[test]
+    fn parallel_and_sequential_conversion_match() {
+        test! {
+            // every sfg type in a shuffled order. With the `parallel` feature
+            // `sfc_to_geoms()` builds these on the rayon thread pool while `SfcIter`
+            // always converts them one at a time on the R thread. CI runs the tests
+            // with the feature on and off.
+            let sfc = List::try_from(R!("set.seed(2)
+                m <- matrix(c(0, 1, 1, 0, 0, 0, 0, 1, 1, 0), ncol = 2)
+                sfg <- list(
+                    structure(c(1, 2), class = c('XY', 'POINT', 'sfg')),
+                    structure(m, class = c('XY', 'MULTIPOINT', 'sfg')),
+                    structure(m, class = c('XY', 'LINESTRING', 'sfg')),
+                    structure(list(m, m * 2), class = c('XY', 'MULTILINESTRING', 'sfg')),
+                    structure(list(m * 4, m + 1), class = c('XY', 'POLYGON', 'sfg')),
+                    structure(list(list(m), list(m * 3)), class = c('XY', 'MULTIPOLYGON', 'sfg')),
+                    NULL
+                )
+                structure(
+                    lapply(sample(rep(seq_along(sfg), 200)), function(i) sfg[[i]]),
+                    class = c('sfc_GEOMETRY', 'sfc')
+                )").unwrap()).unwrap();
 
-        &_ => Robj::from(NULL)
+            let sequential = SfcIter::new(sfc.clone())
+                .map(|geom| geom.unwrap())
+                .collect::<Vec<Option<Geom>>>();
+
+            assert_eq!(sequential.len(), 1400);
+            assert_eq!(geometries(sfc_to_geoms(sfc)), geometries(sequential));
+        }
     }
-}
\ No newline at end of file
+}
+
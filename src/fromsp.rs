@@ -0,0 +1,245 @@
+//! Conversion from {sp} Spatial objects to geo_types
+//!
+//! sp classes are S4 objects whose slots are stored as attributes. Supported
+//! classes, including their `*DataFrame` variants, are:
+//!
+//! - `SpatialPoints`: each row of the `coords` matrix is a `Point`
+//! - `SpatialMultiPoints`: each matrix of `coords` is a `MultiPoint`
+//! - `SpatialLines`: each `Lines` is a `LineString` or `MultiLineString`
+//! - `SpatialPolygons`: each `Polygons` is a `Polygon` or `MultiPolygon`
+//!
+//! The `hole` slot of each `Polygon` ring determines whether it is an interior
+//! ring; orientation is never used to infer holes. Holes are assigned to the
+//! exterior ring given by the `comment` attribute of `Polygons` when present,
+//! otherwise to the closest preceding exterior ring.
+use extendr_api::prelude::*;
+use geo_types::{Coord, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon};
+use crate::Geom;
+
+/// Convert an sp Spatial object into a `Vec<Option<Geom>>`.
+pub fn sp_to_geoms(x: Robj) -> Result<Vec<Option<Geom>>> {
+    let cls = x
+        .class()
+        .and_then(|mut cls| cls.next())
+        .unwrap_or("")
+        .to_string();
+
+    if cls.starts_with("SpatialPoints") {
+        let coords = sp_coords(&slot(&x, "coords")?)?;
+        Ok(coords.into_iter().map(|crd| Some(Geom::from(Point::from(crd)))).collect())
+    } else if cls.starts_with("SpatialMultiPoints") {
+        slot_list(&x, "coords")?
+            .iter()
+            .map(|(_, crds)| Ok(Some(Geom::from(MultiPoint::from(sp_coords(&crds)?)))))
+            .collect()
+    } else if cls.starts_with("SpatialLines") {
+        slot_list(&x, "lines")?
+            .iter()
+            .map(|(_, lines)| Ok(Some(sp_lines(&lines)?)))
+            .collect()
+    } else if cls.starts_with("SpatialPolygons") {
+        slot_list(&x, "polygons")?
+            .iter()
+            .map(|(_, polygons)| Ok(Some(sp_polygons(&polygons)?)))
+            .collect()
+    } else {
+        Err(Error::Other(format!("Unsupported sp class `{cls}`")))
+    }
+}
+
+fn slot(x: &Robj, name: &str) -> Result<Robj> {
+    x.get_attrib(name)
+        .ok_or_else(|| Error::Other(format!("Missing `{name}` slot")))
+}
+
+fn slot_list(x: &Robj, name: &str) -> Result<List> {
+    List::try_from(slot(x, name)?)
+}
+
+// reads the x and y columns of a coordinate matrix, additional columns are ignored
+fn sp_coords(x: &Robj) -> Result<Vec<Coord>> {
+    let x = RMatrix::<f64>::from_robj(x)?;
+
+    if x.ncols() < 2 {
+        return Err(Error::Other("Coordinate matrix must have at least 2 columns".to_string()));
+    }
+
+    let n = x.nrows();
+    let data = x.data();
+
+    Ok((0..n).map(|i| Coord { x: data[i], y: data[n + i] }).collect())
+}
+
+// a `Lines` object contains a list of `Line` objects in its `Lines` slot
+fn sp_lines(x: &Robj) -> Result<Geom> {
+    let mut lines = slot_list(x, "Lines")?
+        .iter()
+        .map(|(_, line)| Ok(LineString::new(sp_coords(&slot(&line, "coords")?)?)))
+        .collect::<Result<Vec<LineString>>>()?;
+
+    if lines.len() == 1 {
+        Ok(Geom::from(lines.remove(0)))
+    } else {
+        Ok(Geom::from(MultiLineString::new(lines)))
+    }
+}
+
+// a `Polygons` object contains a list of `Polygon` rings in its `Polygons` slot
+fn sp_polygons(x: &Robj) -> Result<Geom> {
+    let rings = slot_list(x, "Polygons")?
+        .iter()
+        .map(|(_, ring)| {
+            let coords = sp_coords(&slot(&ring, "coords")?)?;
+            let hole = slot(&ring, "hole")?.as_bool().unwrap_or(false);
+            Ok((LineString::new(coords), hole))
+        })
+        .collect::<Result<Vec<(LineString, bool)>>>()?;
+
+    // the comment gives the 1-based index of the exterior ring of each hole
+    // and 0 for exterior rings e.g. "0 1 0"
+    let owners = x
+        .get_attrib("comment")
+        .and_then(|cmt| cmt.as_str().map(String::from))
+        .map(|cmt| {
+            cmt.split_whitespace()
+                .map(|i| i.parse::<usize>().unwrap_or(0))
+                .collect::<Vec<usize>>()
+        })
+        .filter(|owners| owners.len() == rings.len());
+
+    let mut polygons: Vec<(LineString, Vec<LineString>)> = Vec::new();
+    // position of each exterior ring in `polygons` by ring index
+    let mut exterior_pos: Vec<Option<usize>> = vec![None; rings.len()];
+
+    for (i, (ring, hole)) in rings.iter().enumerate() {
+        if !hole {
+            exterior_pos[i] = Some(polygons.len());
+            polygons.push((ring.clone(), Vec::new()));
+        }
+    }
+
+    for (i, (ring, hole)) in rings.into_iter().enumerate() {
+        if !hole {
+            continue;
+        }
+
+        let owner = match &owners {
+            Some(owners) if owners[i] > 0 => exterior_pos.get(owners[i] - 1).copied().flatten(),
+            _ => exterior_pos[..i].iter().rev().find_map(|pos| *pos),
+        };
+
+        match owner {
+            Some(pos) => polygons[pos].1.push(ring),
+            None => return Err(Error::Other(format!("Hole {} has no exterior ring", i + 1))),
+        }
+    }
+
+    let mut polygons = polygons
+        .into_iter()
+        .map(|(exterior, interiors)| Polygon::new(exterior, interiors))
+        .collect::<Vec<Polygon>>();
+
+    if polygons.len() == 1 {
+        Ok(Geom::from(polygons.remove(0)))
+    } else {
+        Ok(Geom::from(MultiPolygon::new(polygons)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::Geometry;
+
+    // sp objects are S4 so their slots are attributes. These fixtures set the
+    // slots directly to avoid depending on sp.
+    fn geometries(x: &str) -> Result<Vec<Option<Geometry>>> {
+        let x = eval_string(&format!(
+            "ring <- function(x, y, hole = FALSE) {{
+                structure(list(), coords = cbind(x, y), hole = hole, class = 'Polygon')
+            }}
+            polygons <- function(rings, comment = NULL) {{
+                x <- structure(list(), Polygons = rings, class = 'Polygons')
+                if (!is.null(comment)) attr(x, 'comment') <- comment
+                x
+            }}
+            {x}"
+        ))?;
+
+        Ok(sp_to_geoms(x)?
+            .into_iter()
+            .map(|geom| geom.map(|geom| geom.geom))
+            .collect())
+    }
+
+    fn square(x: f64, size: f64) -> LineString {
+        LineString::from(vec![(x, 0.0), (x + size, 0.0), (x + size, size), (x, size), (x, 0.0)])
+    }
+
+    // the R expression of `square()` as a `Polygon` ring
+    fn square_ring(x: f64, size: f64, hole: bool) -> String {
+        let hole = if hole { "TRUE" } else { "FALSE" };
+        format!(
+            "ring(c({x}, {x} + {size}, {x} + {size}, {x}, {x}), c(0, 0, {size}, {size}, 0), {hole})"
+        )
+    }
+
+    #[test]
+    fn spatial_points_and_lines() {
+        test! {
+            let pnts = geometries(
+                "structure(list(), coords = cbind(c(1, 2), c(3, 4), c(9, 9)), class = 'SpatialPointsDataFrame')"
+            ).unwrap();
+            assert_eq!(pnts, vec![Some(Point::new(1.0, 3.0).into()), Some(Point::new(2.0, 4.0).into())]);
+
+            let lines = geometries(
+                "line <- function(x) structure(list(), coords = cbind(x, x) * 1, class = 'Line')
+                lines <- function(...) structure(list(), Lines = list(...), class = 'Lines')
+                structure(list(), lines = list(lines(line(0:1)), lines(line(0:1), line(2:4))), class = 'SpatialLines')"
+            ).unwrap();
+
+            let lns = LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]);
+            let lns2 = LineString::from(vec![(2.0, 2.0), (3.0, 3.0), (4.0, 4.0)]);
+            assert_eq!(lines[0], Some(lns.clone().into()));
+            assert_eq!(lines[1], Some(MultiLineString::new(vec![lns, lns2]).into()));
+
+            assert!(geometries("structure(list(), class = 'SpatialGrid')").is_err());
+        }
+    }
+
+    #[test]
+    fn spatial_polygons_assign_holes() {
+        test! {
+            let (outer, hole, other) = (square_ring(0.0, 10.0, false), square_ring(1.0, 2.0, true), square_ring(20.0, 5.0, false));
+
+            // without a comment a hole belongs to the preceding exterior ring
+            let res = geometries(&format!(
+                "structure(list(), polygons = list(polygons(list({outer}, {hole}, {other}))), class = 'SpatialPolygons')"
+            )).unwrap();
+
+            let expected = MultiPolygon::new(vec![
+                Polygon::new(square(0.0, 10.0), vec![square(1.0, 2.0)]),
+                Polygon::new(square(20.0, 5.0), vec![]),
+            ]);
+            assert_eq!(res[0], Some(expected.clone().into()));
+
+            // the comment assigns a hole listed after both exteriors to the first
+            let res = geometries(&format!(
+                "structure(list(), polygons = list(polygons(list({outer}, {other}, {hole}), '0 0 1')), class = 'SpatialPolygonsDataFrame')"
+            )).unwrap();
+            assert_eq!(res[0], Some(expected.into()));
+
+            // a single exterior is a polygon
+            let res = geometries(&format!(
+                "structure(list(), polygons = list(polygons(list({other}))), class = 'SpatialPolygons')"
+            )).unwrap();
+            assert_eq!(res[0], Some(Polygon::new(square(20.0, 5.0), vec![]).into()));
+
+            // a hole with no exterior before it
+            let err = geometries(&format!(
+                "structure(list(), polygons = list(polygons(list({hole}, {outer}))), class = 'SpatialPolygons')"
+            )).unwrap_err();
+            assert!(err.to_string().contains("Hole 1 has no exterior ring"));
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Create geometries from well-known text
+//!
+//! Available with the `wkt` feature. Each string is parsed using the
+//! [wkt](https://docs.rs/wkt) crate. All simple feature types and
+//! `GEOMETRYCOLLECTION` are supported. `NA` strings are missing geometries.
+use extendr_api::prelude::*;
+use geo_types::{Geometry, GeometryCollection, MultiPoint, Point};
+use std::str::FromStr;
+use wkt::Wkt;
+use crate::{Geom, vctrs::geoms_to_rsgeo};
+
+/// Parse a character vector of WKT into a `Vec<Option<Geom>>`. Returns an error
+/// with the position and the start of the string for the first string that
+/// cannot be parsed.
+pub fn wkt_to_geoms(x: Strings) -> Result<Vec<Option<Geom>>> {
+    x.iter()
+        .enumerate()
+        .map(|(i, wkt)| {
+            if wkt.is_na() {
+                return Ok(None);
+            }
+
+            wkt_to_geom(wkt.as_str())
+                .map(Some)
+                .map_err(|e| {
+                    Error::Other(format!(
+                        "Failed to parse WKT at position {}: `{}`\n{e}",
+                        i + 1,
+                        wkt_prefix(wkt.as_str())
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Parse a character vector of WKT into an rsgeo vctr.
+pub fn sfc_from_wkt(x: Strings) -> Result<Robj> {
+    Ok(geoms_to_rsgeo(wkt_to_geoms(x)?))
+}
+
+fn wkt_to_geom(x: &str) -> std::result::Result<Geom, String> {
+    let wkt = Wkt::<f64>::from_str(x.trim()).map_err(|e| e.to_string())?;
+    wkt_to_geometry(wkt).map(Geom::from)
+}
+
+// geo-types has no empty point, sf uses c(NA, NA). Empty points can be nested in
+// a MULTIPOINT or GEOMETRYCOLLECTION so these are converted recursively.
+fn wkt_to_geometry(x: Wkt<f64>) -> std::result::Result<Geometry, String> {
+    match x {
+        Wkt::Point(pnt) => Ok(wkt_point(pnt).into()),
+        Wkt::MultiPoint(pnts) => {
+            Ok(MultiPoint::new(pnts.0.into_iter().map(wkt_point).collect()).into())
+        }
+        Wkt::GeometryCollection(geoms) => {
+            let geoms = geoms
+                .0
+                .into_iter()
+                .map(wkt_to_geometry)
+                .collect::<std::result::Result<Vec<Geometry>, String>>()?;
+            Ok(GeometryCollection(geoms).into())
+        }
+        x => Geometry::try_from(x).map_err(|e| e.to_string()),
+    }
+}
+
+fn wkt_point(x: wkt::types::Point<f64>) -> Point {
+    match x.0 {
+        Some(crd) => Point::new(crd.x, crd.y),
+        None => Point::new(f64::NAN, f64::NAN),
+    }
+}
+
+// the first 20 characters of a string for error messages
+fn wkt_prefix(x: &str) -> String {
+    let prefix = x.chars().take(20).collect::<String>();
+
+    if prefix.len() < x.len() {
+        format!("{prefix}...")
+    } else {
+        prefix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::HasDimensions;
+    use geo_types::{LineString, Polygon};
+
+    fn parse(x: &str) -> Geometry {
+        wkt_to_geom(x).unwrap().geom
+    }
+
+    fn is_empty_point(x: &Geometry) -> bool {
+        matches!(x, Geometry::Point(pnt) if pnt.x().is_nan() && pnt.y().is_nan())
+    }
+
+    #[test]
+    fn messy_whitespace() {
+        assert_eq!(parse("  POINT(1   2)\n"), Point::new(1.0, 2.0).into());
+        assert_eq!(
+            parse("\tLINESTRING ( 0 0 ,1\t1 )  "),
+            LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]).into()
+        );
+        assert_eq!(
+            parse("polygon((0 0,1 0,\n1 1,0 0))"),
+            Polygon::new(LineString::from(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)]), vec![]).into()
+        );
+    }
+
+    #[test]
+    fn empty_keywords() {
+        assert!(is_empty_point(&parse("POINT EMPTY")));
+        assert!(is_empty_point(&parse("  point   empty ")));
+
+        for x in ["MULTIPOINT EMPTY", "LINESTRING EMPTY", "POLYGON EMPTY", "MULTIPOLYGON EMPTY", "GEOMETRYCOLLECTION EMPTY"] {
+            assert!(parse(x).is_empty(), "{x}");
+        }
+
+        // empty points nested in a collection
+        match parse("GEOMETRYCOLLECTION (POINT EMPTY, POINT (1 2), GEOMETRYCOLLECTION (POINT EMPTY))") {
+            Geometry::GeometryCollection(gc) => {
+                assert!(is_empty_point(&gc.0[0]));
+                assert_eq!(gc.0[1], Point::new(1.0, 2.0).into());
+                assert!(matches!(&gc.0[2], Geometry::GeometryCollection(inner) if is_empty_point(&inner.0[0])));
+            }
+            x => panic!("expected a GEOMETRYCOLLECTION, found {x:?}"),
+        }
+    }
+
+    #[test]
+    fn errors_report_the_position() {
+        test! {
+            let geoms = wkt_to_geoms(Strings::try_from(R!("c('POINT (1 2)', NA)").unwrap()).unwrap()).unwrap();
+            assert!(geoms[0].is_some());
+            assert!(geoms[1].is_none());
+
+            let x = Strings::try_from(R!("c('POINT (1 2)', NA, 'POINT (1 2')").unwrap()).unwrap();
+            let err = wkt_to_geoms(x).unwrap_err().to_string();
+            assert!(err.contains("position 3"));
+            assert!(err.contains("`POINT (1 2`"));
+        }
+    }
+}
+
@@ -0,0 +1,54 @@
+//! Interoperability with the geos R package
+//!
+//! geos stores geometries as external pointers in a `geos_geometry` vctr which
+//! cannot be read from Rust. Geometries are exchanged as WKB instead. The list of
+//! raw vectors written by `wkb::geoms_to_wkb()` is read by `geos::geos_read_wkb()`
+//! as is so there is no geos specific writer.
+use extendr_api::prelude::*;
+use crate::{Geom, wkb::wkb_to_geoms};
+
+/// Convert a `geos_geometry` vector into a `Vec<Option<Geom>>`. The WKB is obtained
+/// by calling `geos::geos_write_wkb()` which requires geos to be installed. A list
+/// of raw vectors that was already extracted with `geos_write_wkb()` is also accepted.
+pub fn geos_to_geoms(x: Robj) -> Result<Vec<Option<Geom>>> {
+    if x.inherits("geos_geometry") {
+        let write_wkb = eval_string("geos::geos_write_wkb")?;
+        let wkb = write_wkb.call(pairlist!(x))?;
+        wkb_to_geoms(List::try_from(wkb)?)
+    } else if x.rtype() == Rtype::List {
+        wkb_to_geoms(List::try_from(x)?)
+    } else {
+        Err(Error::Other(
+            "`x` must be a `geos_geometry` vector or a list of raw vectors".to_string()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::{Geometry, Point};
+
+    #[test]
+    fn geos_wkb_fixtures() {
+        test! {
+            // `geos::geos_write_wkb(geos::geos_read_wkt("POINT (1 2)"))` in both byte orders
+            let x = R!("list(
+                as.raw(c(0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0x3f,
+                         0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40)),
+                NULL,
+                as.raw(c(0x00, 0x00, 0x00, 0x00, 0x01, 0x3f, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                         0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00))
+            )").unwrap();
+
+            let geoms = geos_to_geoms(x).unwrap();
+            let expected = Geometry::from(Point::new(1.0, 2.0));
+
+            assert_eq!(geoms[0].clone().unwrap().geom, expected);
+            assert!(geoms[1].is_none());
+            assert_eq!(geoms[2].clone().unwrap().geom, expected);
+
+            assert!(geos_to_geoms(R!("'POINT (1 2)'").unwrap()).is_err());
+        }
+    }
+}
@@ -2,8 +2,34 @@ use extendr_api::prelude::*;
 
 pub mod fromsf;
 pub mod tosf;
+pub mod fromsp;
 pub mod constructors;
+pub mod fromdf;
 pub mod vctrs;
+pub mod crs;
+pub mod meta;
+pub mod measures;
+pub mod ops;
+pub mod predicates;
+pub mod query;
+pub mod wkb;
+pub mod towkt;
+pub mod wk;
+pub mod geos;
+pub mod s2;
+pub mod terra;
+
+#[cfg(feature = "proj")]
+pub mod transform;
+
+#[cfg(feature = "wkt")]
+pub mod fromwkt;
+
+#[cfg(feature = "geojson")]
+pub mod fromgeojson;
+
+#[cfg(feature = "shapefile")]
+pub mod shp;
 
 use geo_types::{
     Geometry, Line, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon, Rect,
@@ -85,6 +111,22 @@ impl Geom {
     }
 }
 
+/// Returns the sf geometry type of a `Geometry` e.g. `"POINT"` or `"MULTIPOLYGON"`.
+/// geo-types only geometries are returned as `"LINE"`, `"RECT"`, and `"TRIANGLE"`.
+pub fn geometry_type(x: &Geometry) -> &'static str {
+    match x {
+        Geometry::Point(_) => "POINT",
+        Geometry::MultiPoint(_) => "MULTIPOINT",
+        Geometry::LineString(_) => "LINESTRING",
+        Geometry::MultiLineString(_) => "MULTILINESTRING",
+        Geometry::Polygon(_) => "POLYGON",
+        Geometry::MultiPolygon(_) => "MULTIPOLYGON",
+        Geometry::GeometryCollection(_) => "GEOMETRYCOLLECTION",
+        Geometry::Line(_) => "LINE",
+        Geometry::Rect(_) => "RECT",
+        Geometry::Triangle(_) => "TRIANGLE",
+    }
+}
 
 // FROM geo-types to Geom
 /// Convert a Geometry enum to a Geom struct
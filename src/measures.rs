@@ -0,0 +1,283 @@
+//! Planar measures of `Geom` structs
+//!
+//! These functions compute measures using geo. Coordinates are treated as
+//! cartesian so results are planar and in the units of the coordinate reference
+//! system. They are _not_ geodesic. For longitude / latitude data use sf or s2.
+//!
+//! The vectorized `rsgeo_*` variants take a `List` of `Geom` pointers and return
+//! an `NA` for missing geometries.
+use extendr_api::prelude::*;
+use geo::{Area, BoundingRect, Closest, ClosestPoint, EuclideanDistance, EuclideanLength};
+use geo_types::{coord, Geometry, Point, Polygon, Rect};
+use crate::{Geom, geoms_ref_from_list, tosf::from_point, vctrs::recycled_len_strict};
+
+/// Calculate the unsigned planar area of a `Geom`. Non-areal geometries such
+/// as points and linestrings have an area of `0.0`.
+pub fn geom_area(x: &Geom) -> f64 {
+    x.geom.unsigned_area()
+}
+
+/// Calculate the unsigned planar area of each `Geom` in a list.
+pub fn rsgeo_area(x: List) -> Doubles {
+    Doubles::from_values(
+        geoms_ref_from_list(x)
+            .into_iter()
+            .map(|geom| match geom {
+                Some(geom) => Rfloat::from(geom_area(geom)),
+                None => Rfloat::na(),
+            })
+    )
+}
+
+/// Calculate the planar length of a `Geom`. The length of a polygon is its
+/// perimeter, i.e. the length of its exterior and interior rings. Points
+/// have a length of `0.0`.
+pub fn geom_length(x: &Geom) -> f64 {
+    geometry_length(&x.geom)
+}
+
+/// Calculate the planar length of each `Geom` in a list.
+pub fn rsgeo_length(x: List) -> Doubles {
+    Doubles::from_values(
+        geoms_ref_from_list(x)
+            .into_iter()
+            .map(|geom| match geom {
+                Some(geom) => Rfloat::from(geom_length(geom)),
+                None => Rfloat::na(),
+            })
+    )
+}
+
+fn geometry_length(x: &Geometry) -> f64 {
+    match x {
+        Geometry::Point(_) => 0.0,
+        Geometry::MultiPoint(_) => 0.0,
+        Geometry::Line(x) => x.euclidean_length(),
+        Geometry::LineString(x) => x.euclidean_length(),
+        Geometry::MultiLineString(x) => x.euclidean_length(),
+        Geometry::Polygon(x) => perimeter(x),
+        Geometry::MultiPolygon(x) => x.iter().map(perimeter).sum(),
+        Geometry::Rect(x) => perimeter(&x.to_polygon()),
+        Geometry::Triangle(x) => perimeter(&x.to_polygon()),
+        Geometry::GeometryCollection(x) => x.iter().map(geometry_length).sum(),
+    }
+}
+
+fn perimeter(x: &Polygon) -> f64 {
+    x.exterior().euclidean_length() +
+        x.interiors().iter().map(|ring| ring.euclidean_length()).sum::<f64>()
+}
+
+/// Calculate the bounding box of a `Geom` as `c(xmin, ymin, xmax, ymax)`.
+/// Empty geometries have a bounding box of `NA`s.
+pub fn geom_bbox(x: &Geom) -> Doubles {
+    bbox_to_doubles(x.geom.bounding_rect())
+}
+
+/// Calculate the bounding box of all `Geom`s in a list as `c(xmin, ymin, xmax, ymax)`.
+/// Missing geometries are skipped. If there are no non-empty geometries the
+/// bounding box is all `NA`s.
+pub fn rsgeo_bbox(x: List) -> Doubles {
+    let geoms = geoms_ref_from_list(x);
+    bbox_to_doubles(union_bbox(geoms.into_iter().flatten()))
+}
+
+// folds the bounding rectangles of geometries into one
+pub(crate) fn union_bbox<'a>(x: impl Iterator<Item = &'a Geom>) -> Option<Rect> {
+    x
+        .filter_map(|geom| geom.geom.bounding_rect())
+        .reduce(|a, b| {
+            Rect::new(
+                coord! {x: a.min().x.min(b.min().x), y: a.min().y.min(b.min().y)},
+                coord! {x: a.max().x.max(b.max().x), y: a.max().y.max(b.max().y)},
+            )
+        })
+}
+
+fn bbox_to_doubles(x: Option<Rect>) -> Doubles {
+    match x {
+        Some(x) => Doubles::from_values([x.min().x, x.min().y, x.max().x, x.max().y]),
+        None => Doubles::from_values([Rfloat::na(); 4]),
+    }
+}
+
+/// Find the point on a `Geom` closest to `(px, py)` and return it as a `POINT` sfg.
+/// If `(px, py)` intersects the geometry, e.g. it is one of the points of a
+/// multipoint, the given point is returned. If the closest point cannot be
+/// determined, e.g. for empty geometries, an empty point is returned.
+pub fn geom_closest_point(x: &Geom, px: f64, py: f64) -> Robj {
+    let pnt = Point::new(px, py);
+
+    let res = match x.geom.closest_point(&pnt) {
+        Closest::Intersection(_) => pnt,
+        Closest::SinglePoint(closest) => closest,
+        Closest::Indeterminate => Point::new(Rfloat::na().inner(), Rfloat::na().inner()),
+    };
+
+    from_point(res)
+}
+
+/// Calculate the planar distance between two `Geom`s. Intersecting
+/// geometries have a distance of `0.0`.
+pub fn geom_distance(a: &Geom, b: &Geom) -> f64 {
+    a.geom.euclidean_distance(&b.geom)
+}
+
+/// Calculate the pairwise distance between the `Geom`s of two lists. A length 1
+/// list is recycled to the length of the other, any other difference in length is
+/// an error. See `vctrs::recycled_len_strict()`. The distance is `NA` where either
+/// geometry is missing.
+pub fn rsgeo_distance(x: List, y: List) -> Result<Doubles> {
+    let x = geoms_ref_from_list(x);
+    let y = geoms_ref_from_list(y);
+    let n = recycled_len_strict(&[("x", x.len()), ("y", y.len())])?;
+
+    Ok(Doubles::from_values((0..n).map(|i| {
+        match (x[i % x.len()], y[i % y.len()]) {
+            (Some(a), Some(b)) => Rfloat::from(geom_distance(a, b)),
+            _ => Rfloat::na(),
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::{GeometryCollection, LineString, MultiPoint, MultiPolygon};
+
+    fn geoms(x: Vec<Option<Geometry>>) -> List {
+        x.into_iter()
+            .map(|geom| match geom {
+                Some(geom) => Geom::from(geom).into_robj(),
+                None => Robj::from(NULL),
+            })
+            .collect::<List>()
+    }
+
+    fn square(x: f64, size: f64) -> LineString {
+        LineString::from(vec![(x, x), (x + size, x), (x + size, x + size), (x, x + size), (x, x)])
+    }
+
+    #[test]
+    fn area_subtracts_holes() {
+        test! {
+            let poly = Polygon::new(square(0.0, 10.0), vec![square(1.0, 2.0)]);
+            assert_eq!(geom_area(&Geom::from(poly.clone())), 96.0);
+
+            // a clockwise exterior has the same unsigned area
+            let mut cw = square(0.0, 10.0);
+            cw.0.reverse();
+            assert_eq!(geom_area(&Geom::from(Polygon::new(cw, vec![]))), 100.0);
+
+            let x = geoms(vec![
+                Some(poly.into()),
+                Some(Point::new(1.0, 1.0).into()),
+                Some(square(0.0, 1.0).into()),
+                None,
+            ]);
+
+            let res = rsgeo_area(x);
+            assert_eq!(res.elt(0), Rfloat::from(96.0));
+            assert_eq!(res.elt(1), Rfloat::from(0.0));
+            assert_eq!(res.elt(2), Rfloat::from(0.0));
+            assert!(res.elt(3).is_na());
+        }
+    }
+
+    #[test]
+    fn length_is_the_perimeter_of_all_rings() {
+        test! {
+            let poly = Polygon::new(square(0.0, 10.0), vec![square(1.0, 2.0)]);
+            assert_eq!(geometry_length(&poly.clone().into()), 48.0);
+
+            let mpoly = MultiPolygon::new(vec![poly.clone(), Polygon::new(square(20.0, 1.0), vec![])]);
+            assert_eq!(geometry_length(&mpoly.into()), 52.0);
+
+            let lns = LineString::from(vec![(0.0, 0.0), (3.0, 4.0)]);
+            let gc = GeometryCollection::new_from(vec![
+                poly.into(),
+                lns.clone().into(),
+                Point::new(1.0, 1.0).into(),
+            ]);
+            assert_eq!(geometry_length(&gc.into()), 53.0);
+
+            let res = rsgeo_length(geoms(vec![Some(lns.into()), Some(Point::new(0.0, 0.0).into()), None]));
+            assert_eq!(res.elt(0), Rfloat::from(5.0));
+            assert_eq!(res.elt(1), Rfloat::from(0.0));
+            assert!(res.elt(2).is_na());
+        }
+    }
+
+    #[test]
+    fn distance_recycles_length_1() {
+        test! {
+            let origin = geoms(vec![Some(Point::new(0.0, 0.0).into())]);
+            let x = geoms(vec![
+                Some(Point::new(3.0, 4.0).into()),
+                None,
+                Some(Polygon::new(square(-1.0, 2.0), vec![]).into()),
+            ]);
+
+            let res = rsgeo_distance(x.clone(), origin.clone()).unwrap();
+            assert_eq!(res.len(), 3);
+            assert_eq!(res.elt(0), Rfloat::from(5.0));
+            assert!(res.elt(1).is_na());
+            // the origin is within the polygon
+            assert_eq!(res.elt(2), Rfloat::from(0.0));
+
+            assert_eq!(rsgeo_distance(origin.clone(), x.clone()).unwrap().len(), 3);
+
+            // zero length only recycles with length 1
+            assert_eq!(rsgeo_distance(List::new(0), origin).unwrap().len(), 0);
+            assert!(rsgeo_distance(List::new(0), x.clone()).is_err());
+
+            let err = rsgeo_distance(x, geoms(vec![None, None])).unwrap_err().to_string();
+            assert_eq!(err, "Can't recycle `y` (size 2) to match `x` (size 3).");
+        }
+    }
+
+    #[test]
+    fn bbox_skips_missing_and_empty_geometries() {
+        test! {
+            let all_na = |x: Doubles| x.iter().all(|v| v.is_na());
+
+            assert!(all_na(rsgeo_bbox(geoms(vec![None, None]))));
+            assert!(all_na(rsgeo_bbox(List::new(0))));
+            assert!(all_na(geom_bbox(&Geom::from(LineString::new(vec![])))));
+
+            let x = geoms(vec![
+                Some(LineString::new(vec![]).into()),
+                None,
+                Some(Point::new(-1.0, 5.0).into()),
+                Some(Polygon::new(square(0.0, 2.0), vec![]).into()),
+            ]);
+
+            let res = rsgeo_bbox(x);
+            assert_eq!(res.as_real_slice(), Some(&[-1.0, 0.0, 2.0, 5.0][..]));
+            assert_eq!(union_bbox(std::iter::empty()), None);
+        }
+    }
+
+    #[test]
+    fn closest_point_hits_projects_and_is_empty() {
+        test! {
+            let coords = |x: Robj| x.as_real_slice().unwrap().to_vec();
+
+            // a point of the multipoint is returned as is
+            let mpnt = Geom::from(MultiPoint::from(vec![(0.0, 0.0), (2.0, 2.0)]));
+            assert_eq!(coords(geom_closest_point(&mpnt, 2.0, 2.0)), vec![2.0, 2.0]);
+            assert_eq!(coords(geom_closest_point(&mpnt, 0.5, 0.0)), vec![0.0, 0.0]);
+
+            // projected onto the linestring
+            let lns = Geom::from(LineString::from(vec![(0.0, 0.0), (10.0, 0.0)]));
+            let res = geom_closest_point(&lns, 3.0, 4.0);
+            assert!(res.inherits("POINT"));
+            assert_eq!(coords(res), vec![3.0, 0.0]);
+
+            let empty = Geom::from(LineString::new(vec![]));
+            let res = coords(geom_closest_point(&empty, 3.0, 4.0));
+            assert_eq!(res.len(), 2);
+            assert!(res.iter().all(|v| Rfloat::from(*v).is_na()));
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Metadata of sfc objects
+//!
+//! Converting an sfc into `Geom`s keeps only the geometries. The `SfcMeta` struct
+//! captures the attributes of the sfc that would otherwise be lost, its crs,
+//! precision, names, and geometry class, so that they can be reapplied with
+//! `tosf::geoms_to_sfc_with_meta()` for a lossless round trip.
+//!
+//! Likewise `SfMeta` captures the attributes of an sf data.frame, its geometry
+//! column and `agr`, to be reapplied with `tosf::geoms_to_sf()`.
+use extendr_api::prelude::*;
+use crate::crs::{Crs, sfc_crs};
+use crate::fromsf::{sf_geometry_column, sfc_names, sfc_precision};
+
+/// The levels of sf's `agr` factor.
+const AGR_LEVELS: [&str; 3] = ["constant", "aggregate", "identity"];
+
+/// The attributes of an sfc object.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SfcMeta {
+    /// the crs, `None` is sf's `NA` crs
+    pub crs: Option<Crs>,
+    /// the precision, sf's default is `0`
+    pub precision: f64,
+    /// the names of the elements if the sfc is named
+    pub names: Option<Vec<String>>,
+    /// the declared sfc class e.g. `"sfc_POLYGON"`
+    pub class: Option<String>,
+}
+
+impl SfcMeta {
+    /// Capture the attributes of an sfc object.
+    pub fn from_sfc(x: &List) -> Result<Self> {
+        let class = x
+            .class()
+            .and_then(|mut cls| cls.find(|cls| cls.starts_with("sfc_")))
+            .map(String::from);
+
+        Ok(SfcMeta {
+            crs: sfc_crs(x)?,
+            precision: sfc_precision(x),
+            names: sfc_names(x),
+            class,
+        })
+    }
+
+    /// Returns the names if there is one for each of `n` elements. If the number of
+    /// geometries changed since the names were captured they no longer apply.
+    pub fn names_for(&self, n: usize) -> Option<&[String]> {
+        self.names
+            .as_deref()
+            .filter(|names| names.len() == n)
+    }
+}
+
+/// The attributes of an sf data.frame.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SfMeta {
+    /// the name of the geometry column
+    pub sf_column: String,
+    /// the attribute-geometry relationship of each attribute column, `None` is `NA`
+    pub agr: Vec<(String, Option<String>)>,
+    /// the attributes of the geometry column
+    pub sfc: SfcMeta,
+}
+
+impl SfMeta {
+    /// Capture the attributes of an sf data.frame.
+    pub fn from_sf(x: &List) -> Result<Self> {
+        let (sf_column, sfc) = sf_geometry_column(x).map_err(|e| Error::Other(e.to_string()))?;
+
+        Ok(SfMeta {
+            sf_column,
+            agr: sf_agr(x),
+            sfc: SfcMeta::from_sfc(&sfc)?,
+        })
+    }
+
+    /// Creates the `agr` factor for the attribute `columns`. Columns without a
+    /// captured relationship are `NA`, as are all columns if `agr` was absent.
+    /// Captured entries for columns that are no longer present are dropped.
+    pub fn agr_for(&self, columns: &[&str]) -> Robj {
+        let codes = columns.iter().map(|col| {
+            let level = self
+                .agr
+                .iter()
+                .find(|(name, _)| name == col)
+                .and_then(|(_, agr)| agr.as_deref())
+                .and_then(|agr| AGR_LEVELS.iter().position(|level| *level == agr));
+
+            match level {
+                Some(i) => Rint::from(i as i32 + 1),
+                None => Rint::na(),
+            }
+        });
+
+        Integers::from_values(codes)
+            .into_robj()
+            .set_attrib("levels", AGR_LEVELS.to_vec())
+            .unwrap()
+            .set_attrib("names", columns.to_vec())
+            .unwrap()
+            .set_class(["factor"])
+            .unwrap()
+    }
+}
+
+// reads the named `agr` factor, an absent or malformed `agr` is empty
+fn sf_agr(x: &List) -> Vec<(String, Option<String>)> {
+    let agr = match x.get_attrib("agr") {
+        Some(agr) if !agr.is_null() => agr,
+        _ => return Vec::new(),
+    };
+
+    let levels = agr
+        .get_attrib("levels")
+        .and_then(|levels| levels.as_str_vector())
+        .unwrap_or_default();
+
+    let (names, codes) = match (agr.names(), agr.as_integer_slice()) {
+        (Some(names), Some(codes)) => (names, codes),
+        _ => return Vec::new(),
+    };
+
+    names
+        .zip(codes)
+        .map(|(name, code)| {
+            // NA_integer_ is i32::MIN
+            let level = if *code < 1 {
+                None
+            } else {
+                levels.get(*code as usize - 1).map(|level| level.to_string())
+            };
+
+            (name.to_string(), level)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Geom;
+    use crate::tosf::{geoms_to_sf, geoms_to_sfc_with_meta};
+    use geo_types::Point;
+
+    fn points(n: usize) -> Vec<Option<Geom>> {
+        (0..n).map(|i| Some(Geom::from(Point::new(i as f64, 0.0)))).collect()
+    }
+
+    #[test]
+    fn sfc_meta_round_trip() {
+        test! {
+            let sfc = List::try_from(R!("structure(
+                list(c(0, 0), c(1, 0)),
+                names = c('a', 'b'),
+                crs = structure(list(input = 'EPSG:3857', wkt = 'PROJCRS[\"WGS 84 / Pseudo-Mercator\",ID[\"EPSG\",3857]]'), class = 'crs'),
+                precision = 100,
+                class = c('sfc_POINT', 'sfc')
+            )")?)?;
+
+            let meta = SfcMeta::from_sfc(&sfc)?;
+            assert_eq!(meta.crs.as_ref().and_then(|crs| crs.epsg()), Some(3857));
+            assert_eq!(meta.precision, 100.0);
+            assert_eq!(meta.names, Some(vec!["a".to_string(), "b".to_string()]));
+            assert_eq!(meta.class.as_deref(), Some("sfc_POINT"));
+
+            let res = List::try_from(geoms_to_sfc_with_meta(points(2), &meta))?;
+            assert_eq!(SfcMeta::from_sfc(&res)?, meta);
+
+            // names no longer apply once the number of geometries changes
+            let res = List::try_from(geoms_to_sfc_with_meta(points(3), &meta))?;
+            let res_meta = SfcMeta::from_sfc(&res)?;
+            assert_eq!(res_meta.names, None);
+            assert_eq!(res_meta.crs, meta.crs);
+            assert_eq!(res_meta.precision, 100.0);
+            assert_eq!(meta.names_for(3), None);
+        }
+    }
+
+    fn sf_fixture() -> Result<List> {
+        List::try_from(R!("structure(
+            list(
+                id = c(1L, 2L),
+                name = c('a', 'b'),
+                geom = structure(list(c(0, 0), c(1, 0)), crs = NULL, precision = 0, class = c('sfc_POINT', 'sfc'))
+            ),
+            row.names = c(1L, 2L),
+            sf_column = 'geom',
+            agr = factor(c(id = 'identity', name = 'constant'), levels = c('constant', 'aggregate', 'identity')),
+            class = c('sf', 'data.frame')
+        )")?)
+    }
+
+    #[test]
+    fn agr_levels_and_names() {
+        test! {
+            let meta = SfMeta::from_sf(&sf_fixture()?)?;
+            assert_eq!(meta.sf_column, "geom");
+            assert_eq!(
+                meta.agr,
+                vec![
+                    ("id".to_string(), Some("identity".to_string())),
+                    ("name".to_string(), Some("constant".to_string())),
+                ]
+            );
+
+            // `renamed` has no captured relationship and `id` is no longer present
+            let agr = meta.agr_for(&["name", "renamed"]);
+            assert_eq!(agr, R!("factor(c(name = 'constant', renamed = NA), levels = c('constant', 'aggregate', 'identity'))")?);
+
+            // all columns are NA when there was no agr
+            let sf = sf_fixture()?.into_robj().set_attrib("agr", NULL)?;
+            let meta = SfMeta::from_sf(&List::try_from(sf)?)?;
+            assert!(meta.agr.is_empty());
+            assert_eq!(
+                meta.agr_for(&["id", "name"]),
+                R!("factor(c(id = NA, name = NA), levels = c('constant', 'aggregate', 'identity'))")?
+            );
+        }
+    }
+
+    #[test]
+    fn sf_round_trip() {
+        test! {
+            let meta = SfMeta::from_sf(&sf_fixture()?)?;
+
+            // `id` is dropped and `name` renamed to `label`
+            let attributes = List::from_names_and_values(["label"], [R!("c('a', 'b')")?])?;
+            let sf = List::try_from(geoms_to_sf(points(2), attributes, &meta)?)?;
+
+            assert!(sf.inherits("sf"));
+            assert_eq!(sf.names().unwrap().collect::<Vec<&str>>(), vec!["label", "geom"]);
+            assert_eq!(sf.get_attrib("row.names"), Some(R!("c(1L, 2L)")?));
+
+            let res = SfMeta::from_sf(&sf)?;
+            assert_eq!(res.sf_column, "geom");
+            assert_eq!(res.agr, vec![("label".to_string(), None)]);
+            assert_eq!(res.sfc.class.as_deref(), Some("sfc_POINT"));
+
+            let attributes = List::from_names_and_values(["label"], [R!("c('a', 'b', 'c')")?])?;
+            let msg = geoms_to_sf(points(2), attributes, &meta).unwrap_err().to_string();
+            assert!(msg.contains("Column `label` has length 3 but there are 2 geometries"), "{msg}");
+        }
+    }
+}
@@ -0,0 +1,577 @@
+//! Geometry operations on `Geom` structs
+//!
+//! These functions wrap algorithms from geo. Scalar functions take and return a
+//! `Geom`. The vectorized `rsgeo_*` variants take a `List` of `Geom` pointers and
+//! return a new rsgeo vctr. Missing geometries are preserved as `NULL`.
+use extendr_api::prelude::*;
+use geo::orient::{Direction, Orient};
+use geo::{
+    BoundingRect, Centroid, ConvexHull, CoordsIter, Densify, MinimumRotatedRect, Rotate, Scale,
+    Simplify, SimplifyVw, Translate, Validation,
+};
+use geo_types::{
+    Coord, Geometry, GeometryCollection, Line, LineString, MultiLineString, MultiPoint,
+    MultiPolygon, Point, Polygon, Triangle
+};
+use crate::{
+    Geom, geoms_from_list, geoms_ref_from_list,
+    fromdf::close_ring,
+    tosf::{from_multilinestring, from_multipoint, from_polygon},
+    vctrs::{as_rsgeo_vctr, geom_to_robj, geoms_to_rsgeo, recycled_len_strict},
+};
+
+/// Simplify a `Geom` using the Ramer–Douglas–Peucker algorithm. Linestrings and
+/// polygons are simplified. Other geometries such as points and multipoints are
+/// returned unchanged.
+pub fn geom_simplify(x: Geom, epsilon: f64) -> Geom {
+    let geom: Geometry = match x.geom {
+        Geometry::LineString(x) => x.simplify(&epsilon).into(),
+        Geometry::MultiLineString(x) => x.simplify(&epsilon).into(),
+        Geometry::Polygon(x) => x.simplify(&epsilon).into(),
+        Geometry::MultiPolygon(x) => x.simplify(&epsilon).into(),
+        geom => geom,
+    };
+
+    Geom::from(geom)
+}
+
+/// Simplify each `Geom` in a list using `geom_simplify()`.
+pub fn rsgeo_simplify(x: List, epsilon: f64) -> Robj {
+    let res = geoms_from_list(x)
+        .into_iter()
+        .map(|geom| geom.map(|geom| geom_simplify(geom, epsilon)))
+        .collect::<Vec<Option<Geom>>>();
+
+    geoms_to_rsgeo(res)
+}
+
+/// Simplify a `Geom` using the Visvalingam–Whyatt algorithm. Unlike
+/// `geom_simplify()`, `epsilon` is an area threshold: vertices forming a triangle
+/// with an area smaller than `epsilon` are removed. Linestrings and polygons are
+/// simplified. Other geometries such as points and multipoints are returned
+/// unchanged.
+pub fn geom_simplify_vw(x: Geom, epsilon: f64) -> Geom {
+    let geom: Geometry = match x.geom {
+        Geometry::LineString(x) => x.simplify_vw(&epsilon).into(),
+        Geometry::MultiLineString(x) => x.simplify_vw(&epsilon).into(),
+        Geometry::Polygon(x) => x.simplify_vw(&epsilon).into(),
+        Geometry::MultiPolygon(x) => x.simplify_vw(&epsilon).into(),
+        geom => geom,
+    };
+
+    Geom::from(geom)
+}
+
+/// Simplify each `Geom` in a list using `geom_simplify_vw()`.
+pub fn rsgeo_simplify_vw(x: List, epsilon: f64) -> Robj {
+    let res = geoms_from_list(x)
+        .into_iter()
+        .map(|geom| geom.map(|geom| geom_simplify_vw(geom, epsilon)))
+        .collect::<Vec<Option<Geom>>>();
+
+    geoms_to_rsgeo(res)
+}
+
+/// Densify a `Geom` by adding vertices so that no segment is longer than
+/// `max_distance`. Applies to linestrings, multilinestrings, and the rings of
+/// polygons. Points and multipoints are returned unchanged.
+pub fn geom_densify(x: Geom, max_distance: f64) -> Geom {
+    let geom: Geometry = match x.geom {
+        Geometry::Line(x) => x.densify(max_distance).into(),
+        Geometry::LineString(x) => x.densify(max_distance).into(),
+        Geometry::MultiLineString(x) => x.densify(max_distance).into(),
+        Geometry::Polygon(x) => x.densify(max_distance).into(),
+        Geometry::MultiPolygon(x) => x.densify(max_distance).into(),
+        Geometry::Rect(x) => x.densify(max_distance).into(),
+        Geometry::Triangle(x) => x.densify(max_distance).into(),
+        geom => geom,
+    };
+
+    Geom::from(geom)
+}
+
+/// Densify each `Geom` in a list using `geom_densify()`.
+pub fn rsgeo_densify(x: List, max_distance: f64) -> Robj {
+    let res = geoms_from_list(x)
+        .into_iter()
+        .map(|geom| geom.map(|geom| geom_densify(geom, max_distance)))
+        .collect::<Vec<Option<Geom>>>();
+
+    geoms_to_rsgeo(res)
+}
+
+/// Compute the convex hull of a `Geom` and return it as a `POLYGON` sfg.
+/// The convex hull of an empty geometry or a single point is an empty polygon.
+pub fn geom_convex_hull(x: &Geom) -> Robj {
+    if x.geom.coords_count() < 2 {
+        return from_polygon(empty_polygon());
+    }
+
+    from_polygon(x.geom.convex_hull())
+}
+
+/// Compute the convex hull of all coordinates of every `Geom` in a list and
+/// return it as a single `POLYGON` sfg. Missing geometries are skipped.
+pub fn rsgeo_convex_hull(x: List) -> Robj {
+    let coords = geoms_ref_from_list(x)
+        .into_iter()
+        .flatten()
+        .flat_map(|geom| geom.geom.coords_iter())
+        .collect::<Vec<Coord>>();
+
+    if coords.len() < 2 {
+        return from_polygon(empty_polygon());
+    }
+
+    from_polygon(MultiPoint::from(coords).convex_hull())
+}
+
+/// Compute the envelope of a `Geom`, its axis-aligned bounding box, and return it
+/// as a closed `POLYGON` sfg with 5 coordinates. This is equivalent to
+/// `sf::st_as_sfc(sf::st_bbox(x))`. Empty geometries return an empty polygon.
+pub fn geom_envelope(x: &Geom) -> Robj {
+    match x.geom.bounding_rect() {
+        Some(rect) => from_polygon(rect.to_polygon()),
+        None => from_polygon(empty_polygon()),
+    }
+}
+
+/// Compute the minimum-area rotated rectangle enclosing a `Geom` and return it as a
+/// `POLYGON` sfg. The rectangle is computed from the convex hull so it is never
+/// larger than the envelope from `geom_envelope()`. Empty geometries return an
+/// empty polygon.
+pub fn geom_minimum_rotated_rect(x: &Geom) -> Robj {
+    if x.geom.coords_count() == 0 {
+        return from_polygon(empty_polygon());
+    }
+
+    match x.geom.minimum_rotated_rect() {
+        Some(rect) => from_polygon(rect),
+        None => from_polygon(empty_polygon()),
+    }
+}
+
+fn empty_polygon() -> Polygon {
+    Polygon::new(LineString::new(vec![]), vec![])
+}
+
+/// Compute the topological boundary of a `Geom` and return it as an sfg. The
+/// boundary of a polygon is a `MULTILINESTRING` of its rings. A multipolygon's
+/// rings are collected into a single `MULTILINESTRING`, polygon by polygon with each
+/// exterior ring followed by its interior rings; an empty multipolygon has an empty
+/// `MULTILINESTRING` boundary. The boundary of a linestring is a `MULTIPOINT` of
+/// its endpoints which is empty if it is closed. For multilinestrings the mod-2
+/// rule applies: endpoints shared by an even number of linestrings are not part of
+/// the boundary. Points and multipoints have an empty boundary, returned as an
+/// empty `MULTIPOINT`. As in GEOS, the boundary of a geometry collection is not
+/// defined and is an error.
+pub fn geom_boundary(x: &Geom) -> Result<Robj> {
+    let res = match &x.geom {
+        Geometry::Polygon(x) => from_multilinestring(polygon_rings(x)),
+        Geometry::MultiPolygon(x) => from_multilinestring(MultiLineString::new(
+            x.iter().flat_map(|poly| polygon_rings(poly).0).collect(),
+        )),
+        Geometry::Rect(x) => from_multilinestring(polygon_rings(&x.to_polygon())),
+        Geometry::Triangle(x) => from_multilinestring(polygon_rings(&x.to_polygon())),
+        Geometry::Line(x) => from_multipoint(MultiPoint::new(vec![x.start.into(), x.end.into()])),
+        Geometry::LineString(x) => from_multipoint(linestring_endpoints(std::slice::from_ref(x))),
+        Geometry::MultiLineString(x) => from_multipoint(linestring_endpoints(&x.0)),
+        Geometry::Point(_) | Geometry::MultiPoint(_) => from_multipoint(MultiPoint::new(vec![])),
+        Geometry::GeometryCollection(_) => {
+            return Err(Error::Other("The boundary of a geometry collection is not defined".to_string()))
+        }
+    };
+
+    Ok(res)
+}
+
+fn polygon_rings(x: &Polygon) -> MultiLineString {
+    if x.exterior().0.is_empty() {
+        return MultiLineString::new(vec![]);
+    }
+
+    let mut rings = Vec::with_capacity(x.interiors().len() + 1);
+    rings.push(x.exterior().clone());
+    rings.extend(x.interiors().iter().cloned());
+    MultiLineString::new(rings)
+}
+
+// endpoints that occur an odd number of times, closed linestrings have none
+fn linestring_endpoints(x: &[LineString]) -> MultiPoint {
+    let mut endpoints: Vec<(Coord, usize)> = Vec::new();
+
+    let ends = x
+        .iter()
+        .filter(|lns| lns.0.len() >= 2 && !lns.is_closed())
+        .flat_map(|lns| [lns.0[0], lns.0[lns.0.len() - 1]]);
+
+    for crd in ends {
+        match endpoints.iter_mut().find(|(c, _)| *c == crd) {
+            Some((_, n)) => *n += 1,
+            None => endpoints.push((crd, 1)),
+        }
+    }
+
+    endpoints
+        .into_iter()
+        .filter(|(_, n)| n % 2 == 1)
+        .map(|(crd, _)| Point::from(crd))
+        .collect()
+}
+
+/// Decompose a `Geom` into its members. Geometry collections are flattened
+/// recursively. If `multipart` is true multipoints, multilinestrings, and
+/// multipolygons are also split into their single geometries. Any other geometry
+/// is returned as is.
+pub fn geom_flatten(x: Geom, multipart: bool) -> Vec<Geom> {
+    let mut res = Vec::new();
+    flatten_geometry(x.geom, multipart, &mut res);
+    res
+}
+
+/// Flatten each `Geom` in a list using `geom_flatten()` and combine the members
+/// into a single rsgeo vctr. Missing geometries are preserved as `NULL`.
+pub fn rsgeo_flatten(x: List, multipart: bool) -> Robj {
+    let res = geoms_from_list(x)
+        .into_iter()
+        .flat_map(|geom| match geom {
+            Some(geom) => geom_flatten(geom, multipart).into_iter().map(Some).collect(),
+            None => vec![None],
+        })
+        .collect::<Vec<Option<Geom>>>();
+
+    geoms_to_rsgeo(res)
+}
+
+/// Explode a multipart `Geom` into its single part components: the points of a
+/// multipoint, the linestrings of a multilinestring, or the polygons of a
+/// multipolygon. Single part geometries are returned as a one element vector.
+/// Unlike `geom_flatten()` geometry collections are not decomposed.
+pub fn geom_explode(x: Geom) -> Vec<Geom> {
+    match x.geom {
+        Geometry::MultiPoint(x) => x.0.into_iter().map(Geom::from).collect(),
+        Geometry::MultiLineString(x) => x.0.into_iter().map(Geom::from).collect(),
+        Geometry::MultiPolygon(x) => x.0.into_iter().map(Geom::from).collect(),
+        geom => vec![Geom::from(geom)],
+    }
+}
+
+/// Explode each `Geom` in a list using `geom_explode()`. Returns a named list with
+/// the exploded rsgeo vctr `geometry` and an integer vector `id` of the position
+/// of the feature each component came from so that attributes can be rejoined.
+/// Missing geometries are preserved as `NULL`.
+pub fn rsgeo_explode(x: List) -> Robj {
+    let mut ids: Vec<i32> = Vec::with_capacity(x.len());
+    let mut res: Vec<Option<Geom>> = Vec::with_capacity(x.len());
+
+    for (i, geom) in geoms_from_list(x).into_iter().enumerate() {
+        match geom {
+            Some(geom) => {
+                let parts = geom_explode(geom);
+                ids.extend(std::iter::repeat(i as i32 + 1).take(parts.len()));
+                res.extend(parts.into_iter().map(Some));
+            }
+            None => {
+                ids.push(i as i32 + 1);
+                res.push(None);
+            }
+        }
+    }
+
+    List::from_names_and_values(["geometry", "id"], [geoms_to_rsgeo(res), ids.into_robj()])
+        .unwrap()
+        .into_robj()
+}
+
+fn flatten_geometry(x: Geometry, multipart: bool, res: &mut Vec<Geom>) {
+    match x {
+        Geometry::GeometryCollection(x) => x
+            .0
+            .into_iter()
+            .for_each(|geom| flatten_geometry(geom, multipart, res)),
+        Geometry::MultiPoint(x) if multipart => {
+            res.extend(x.0.into_iter().map(Geom::from))
+        }
+        Geometry::MultiLineString(x) if multipart => {
+            res.extend(x.0.into_iter().map(Geom::from))
+        }
+        Geometry::MultiPolygon(x) if multipart => {
+            res.extend(x.0.into_iter().map(Geom::from))
+        }
+        geom => res.push(Geom::from(geom)),
+    }
+}
+
+/// Explode a linestring into its segments. Returns an `rs_LINESTRING` vctr where
+/// each element is a linestring of 2 consecutive coordinates. Multilinestrings are
+/// exploded across all of their linestrings. A linestring with fewer than 2
+/// coordinates has no segments. Errors for any other geometry type.
+pub fn geom_segments(x: &Geom) -> Result<Robj> {
+    let lines: Vec<&LineString> = match &x.geom {
+        Geometry::LineString(x) => vec![x],
+        Geometry::MultiLineString(x) => x.0.iter().collect(),
+        _ => {
+            return Err(Error::Other(
+                "`x` must be a linestring or multilinestring".to_string(),
+            ))
+        }
+    };
+
+    let segments = lines
+        .into_iter()
+        .flat_map(|lns| lns.lines())
+        .map(|seg| LineString::new(vec![seg.start, seg.end]))
+        .collect::<Vec<LineString>>();
+
+    let mut res = List::new(segments.len());
+
+    for (i, seg) in segments.into_iter().enumerate() {
+        res.set_elt(i, geom_to_robj(Geom::from(seg)))?;
+    }
+
+    as_rsgeo_vctr(res, "linestring")
+}
+
+/// Attempt to repair an invalid polygon or multipolygon. Valid geometries and
+/// geometries of other types are returned unchanged. The following are repaired:
+///
+/// - rings that are not closed are closed by repeating their first coordinate
+/// - repeated consecutive coordinates are removed
+/// - interior rings with fewer than 4 coordinates after the above are dropped
+/// - rings are oriented with a counter-clockwise exterior and clockwise interiors
+///
+/// Other problems such as self-intersections, non-finite coordinates, or an
+/// exterior ring with fewer than 4 coordinates cannot be repaired and return an
+/// error describing the remaining problem.
+pub fn geom_make_valid(x: Geom) -> Result<Geom> {
+    if x.geom.is_valid() {
+        return Ok(x);
+    }
+
+    let geom: Geometry = match x.geom {
+        Geometry::Polygon(x) => repair_polygon(x).into(),
+        Geometry::MultiPolygon(x) => {
+            MultiPolygon::new(x.0.into_iter().map(repair_polygon).collect()).into()
+        }
+        geom => geom,
+    };
+
+    match geom.check_validation() {
+        Ok(()) => Ok(Geom::from(geom)),
+        Err(e) => Err(Error::Other(format!("Unable to make the geometry valid: {e}"))),
+    }
+}
+
+/// Repair each `Geom` in a list using `geom_make_valid()`. Errors with the
+/// position of the first geometry that cannot be repaired.
+pub fn rsgeo_make_valid(x: List) -> Result<Robj> {
+    let res = geoms_from_list(x)
+        .into_iter()
+        .enumerate()
+        .map(|(i, geom)| {
+            geom.map(geom_make_valid)
+                .transpose()
+                .map_err(|e| Error::Other(format!("Element {}: {e}", i + 1)))
+        })
+        .collect::<Result<Vec<Option<Geom>>>>()?;
+
+    Ok(geoms_to_rsgeo(res))
+}
+
+fn repair_polygon(x: Polygon) -> Polygon {
+    let (exterior, interiors) = x.into_inner();
+
+    let interiors = interiors
+        .into_iter()
+        .map(repair_ring)
+        .filter(|ring| ring.0.len() >= 4)
+        .collect();
+
+    Polygon::new(repair_ring(exterior), interiors).orient(Direction::Default)
+}
+
+fn repair_ring(x: LineString) -> LineString {
+    let mut coords = x.0;
+    coords.dedup();
+    LineString::new(close_ring(coords))
+}
+
+/// Reverse the coordinate order of a `Geom`. Linestrings change direction and the
+/// winding order of polygon rings, both exterior and interior, is reversed.
+/// Points and multipoints are returned unchanged.
+pub fn geom_reverse(x: Geom) -> Geom {
+    Geom::from(reverse_geometry(x.geom))
+}
+
+/// Reverse each `Geom` in a list using `geom_reverse()`.
+pub fn rsgeo_reverse(x: List) -> Robj {
+    let res = geoms_from_list(x)
+        .into_iter()
+        .map(|geom| geom.map(geom_reverse))
+        .collect::<Vec<Option<Geom>>>();
+
+    geoms_to_rsgeo(res)
+}
+
+fn reverse_geometry(x: Geometry) -> Geometry {
+    match x {
+        Geometry::Line(x) => Line::new(x.end, x.start).into(),
+        Geometry::LineString(x) => reverse_linestring(x).into(),
+        Geometry::MultiLineString(x) => {
+            MultiLineString::new(x.0.into_iter().map(reverse_linestring).collect()).into()
+        }
+        Geometry::Polygon(x) => reverse_polygon(x).into(),
+        Geometry::MultiPolygon(x) => {
+            MultiPolygon::new(x.0.into_iter().map(reverse_polygon).collect()).into()
+        }
+        Geometry::Rect(x) => reverse_polygon(x.to_polygon()).into(),
+        Geometry::Triangle(x) => Triangle::new(x.2, x.1, x.0).into(),
+        Geometry::GeometryCollection(x) => {
+            GeometryCollection(x.0.into_iter().map(reverse_geometry).collect()).into()
+        }
+        geom => geom,
+    }
+}
+
+fn reverse_linestring(mut x: LineString) -> LineString {
+    x.0.reverse();
+    x
+}
+
+fn reverse_polygon(x: Polygon) -> Polygon {
+    let (exterior, interiors) = x.into_inner();
+
+    Polygon::new(
+        reverse_linestring(exterior),
+        interiors.into_iter().map(reverse_linestring).collect(),
+    )
+}
+
+/// Shift every coordinate of a `Geom` by `dx` and `dy`.
+pub fn geom_translate(x: Geom, dx: f64, dy: f64) -> Geom {
+    Geom::from(x.geom.translate(dx, dy))
+}
+
+/// Translate each `Geom` in a list using `geom_translate()`. `x`, `dx`, and `dy`
+/// are recycled to a common length so a single offset can move every geometry.
+/// As in vctrs, each must be length 1 or the common length otherwise an error is
+/// returned. If an offset is `NA` the result is a missing geometry.
+pub fn rsgeo_translate(x: List, dx: Doubles, dy: Doubles) -> Result<Robj> {
+    let x = geoms_ref_from_list(x);
+    let n = recycled_len_strict(&[("x", x.len()), ("dx", dx.len()), ("dy", dy.len())])?;
+
+    let res = (0..n)
+        .map(|i| {
+            let dxi = dx.elt(i % dx.len());
+            let dyi = dy.elt(i % dy.len());
+
+            match x[i % x.len()] {
+                Some(geom) if !dxi.is_na() && !dyi.is_na() => {
+                    Some(geom_translate(geom.clone(), dxi.inner(), dyi.inner()))
+                }
+                _ => None,
+            }
+        })
+        .collect::<Vec<Option<Geom>>>();
+
+    Ok(geoms_to_rsgeo(res))
+}
+
+/// Rotate a `Geom` by `degrees` counter-clockwise around `origin`. If `origin` is
+/// `None` the geometry is rotated around its centroid, matching sf. Empty
+/// geometries, which have no centroid, are returned unchanged.
+pub fn geom_rotate(x: Geom, degrees: f64, origin: Option<(f64, f64)>) -> Geom {
+    match affine_origin(&x.geom, origin) {
+        Some(origin) => Geom::from(x.geom.rotate_around_point(degrees, origin)),
+        None => x,
+    }
+}
+
+/// Rotate each `Geom` in a list using `geom_rotate()`. When `origin` is `None` each
+/// geometry is rotated around its own centroid.
+pub fn rsgeo_rotate(x: List, degrees: f64, origin: Option<(f64, f64)>) -> Robj {
+    let res = geoms_from_list(x)
+        .into_iter()
+        .map(|geom| geom.map(|geom| geom_rotate(geom, degrees, origin)))
+        .collect::<Vec<Option<Geom>>>();
+
+    geoms_to_rsgeo(res)
+}
+
+/// Scale a `Geom` by `xfact` and `yfact` relative to `origin`. If `origin` is `None`
+/// the geometry is scaled around its centroid. Empty geometries are returned
+/// unchanged.
+pub fn geom_scale(x: Geom, xfact: f64, yfact: f64, origin: Option<(f64, f64)>) -> Geom {
+    match affine_origin(&x.geom, origin) {
+        Some(origin) => Geom::from(x.geom.scale_around_point(xfact, yfact, origin)),
+        None => x,
+    }
+}
+
+/// Scale each `Geom` in a list using `geom_scale()`.
+pub fn rsgeo_scale(x: List, xfact: f64, yfact: f64, origin: Option<(f64, f64)>) -> Robj {
+    let res = geoms_from_list(x)
+        .into_iter()
+        .map(|geom| geom.map(|geom| geom_scale(geom, xfact, yfact, origin)))
+        .collect::<Vec<Option<Geom>>>();
+
+    geoms_to_rsgeo(res)
+}
+
+// the origin of a rotation or scaling: the given point or else the centroid
+fn affine_origin(x: &Geometry, origin: Option<(f64, f64)>) -> Option<Point> {
+    match origin {
+        Some((x, y)) => Some(Point::new(x, y)),
+        None => x.centroid(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translate_recycles_like_vctrs() {
+        test! {
+            let pnts = List::try_from(
+                geoms_to_rsgeo(vec![
+                    Some(Geom::from(Point::new(0.0, 0.0))),
+                    Some(Geom::from(Point::new(1.0, 1.0))),
+                    None,
+                ])
+            ).unwrap();
+
+            let res = rsgeo_translate(pnts.clone(), Doubles::from_values([1.0]), Doubles::from_values([2.0, 3.0, 4.0])).unwrap();
+            let geoms = geoms_from_list(List::try_from(res).unwrap());
+            assert_eq!(geoms[1].clone().unwrap().geom, Point::new(2.0, 4.0).into());
+            assert!(geoms[2].is_none());
+
+            let err = rsgeo_translate(pnts, Doubles::from_values([1.0, 2.0]), Doubles::from_values([0.0]))
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("`dx` (size 2)"));
+        }
+    }
+
+    #[test]
+    fn boundary_of_points_and_collections() {
+        test! {
+            let dim = |x: &Robj| x.dim().unwrap().iter().map(|d| d.inner()).collect::<Vec<i32>>();
+
+            for geom in [Geometry::from(Point::new(1.0, 2.0)), MultiPoint::from(vec![(0.0, 0.0)]).into()] {
+                let res = geom_boundary(&Geom::from(geom)).unwrap();
+                assert!(res.inherits("MULTIPOINT"));
+                assert_eq!(dim(&res), vec![0, 2]);
+            }
+
+            let lns = LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]);
+            let res = geom_boundary(&Geom::from(lns)).unwrap();
+            assert!(res.inherits("MULTIPOINT"));
+            assert_eq!(res.as_real_slice(), Some(&[0.0, 1.0, 0.0, 1.0][..]));
+
+            let gc = Geometry::GeometryCollection(GeometryCollection::new_from(vec![Point::new(1.0, 2.0).into()]));
+            let err = geom_boundary(&Geom::from(gc)).unwrap_err().to_string();
+            assert_eq!(err, "The boundary of a geometry collection is not defined");
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Binary and unary predicates of `Geom` structs
+//!
+//! Scalar predicates return a `bool`. The vectorized `rsgeo_*` variants take a
+//! `List` of `Geom` pointers and return a logical vector with `NA` for missing
+//! geometries.
+use extendr_api::prelude::*;
+use geo::{HasDimensions, Intersects, Validation};
+use geo_types::{Coord, Geometry, LineString, Polygon};
+use crate::{Geom, geoms_ref_from_list, vctrs::recycled_len_strict};
+
+/// Determine whether two `Geom`s intersect. The predicate is symmetric
+/// and empty geometries never intersect.
+pub fn geom_intersects(a: &Geom, b: &Geom) -> bool {
+    if a.geom.is_empty() || b.geom.is_empty() {
+        return false;
+    }
+
+    a.geom.intersects(&b.geom)
+}
+
+/// Determine whether the `Geom`s of two lists intersect pairwise. A length 1 list
+/// is recycled to the length of the other, any other difference in length is an
+/// error. See `vctrs::recycled_len_strict()`.
+pub fn rsgeo_intersects(x: List, y: List) -> Result<Logicals> {
+    let x = geoms_ref_from_list(x);
+    let y = geoms_ref_from_list(y);
+    let n = recycled_len_strict(&[("x", x.len()), ("y", y.len())])?;
+
+    Ok(Logicals::from_values((0..n).map(|i| {
+        match (x[i % x.len()], y[i % y.len()]) {
+            (Some(a), Some(b)) => Rbool::from(geom_intersects(a, b)),
+            _ => Rbool::na(),
+        }
+    })))
+}
+
+/// Compare two geometries coordinate by coordinate. Coordinates are equal if both
+/// x and y differ by no more than `tolerance`. The parts of multipart geometries and
+/// the interior rings of polygons can be in any order. Geometries of different
+/// types are never equal.
+pub fn geom_equals_exact(a: &Geom, b: &Geom, tolerance: f64) -> bool {
+    geometry_equals(&a.geom, &b.geom, tolerance)
+}
+
+fn geometry_equals(a: &Geometry, b: &Geometry, tol: f64) -> bool {
+    match (a, b) {
+        (Geometry::Point(a), Geometry::Point(b)) => coord_equals(a.0, b.0, tol),
+        (Geometry::Line(a), Geometry::Line(b)) => {
+            coord_equals(a.start, b.start, tol) && coord_equals(a.end, b.end, tol)
+        }
+        (Geometry::LineString(a), Geometry::LineString(b)) => linestring_equals(a, b, tol),
+        (Geometry::Polygon(a), Geometry::Polygon(b)) => polygon_equals(a, b, tol),
+        (Geometry::MultiPoint(a), Geometry::MultiPoint(b)) => {
+            parts_equal(&a.0, &b.0, |a, b| coord_equals(a.0, b.0, tol))
+        }
+        (Geometry::MultiLineString(a), Geometry::MultiLineString(b)) => {
+            parts_equal(&a.0, &b.0, |a, b| linestring_equals(a, b, tol))
+        }
+        (Geometry::MultiPolygon(a), Geometry::MultiPolygon(b)) => {
+            parts_equal(&a.0, &b.0, |a, b| polygon_equals(a, b, tol))
+        }
+        (Geometry::GeometryCollection(a), Geometry::GeometryCollection(b)) => {
+            parts_equal(&a.0, &b.0, |a, b| geometry_equals(a, b, tol))
+        }
+        (Geometry::Rect(a), Geometry::Rect(b)) => {
+            coord_equals(a.min(), b.min(), tol) && coord_equals(a.max(), b.max(), tol)
+        }
+        (Geometry::Triangle(a), Geometry::Triangle(b)) => a
+            .to_array()
+            .iter()
+            .zip(b.to_array().iter())
+            .all(|(a, b)| coord_equals(*a, *b, tol)),
+        _ => false,
+    }
+}
+
+// NaN coordinates, i.e. empty points, are equal to each other
+fn coord_equals(a: Coord, b: Coord, tol: f64) -> bool {
+    let equals = |a: f64, b: f64| (a.is_nan() && b.is_nan()) || (a - b).abs() <= tol;
+    equals(a.x, b.x) && equals(a.y, b.y)
+}
+
+fn linestring_equals(a: &LineString, b: &LineString, tol: f64) -> bool {
+    a.0.len() == b.0.len() && a.0.iter().zip(b.0.iter()).all(|(a, b)| coord_equals(*a, *b, tol))
+}
+
+fn polygon_equals(a: &Polygon, b: &Polygon, tol: f64) -> bool {
+    linestring_equals(a.exterior(), b.exterior(), tol) &&
+        parts_equal(a.interiors(), b.interiors(), |a, b| linestring_equals(a, b, tol))
+}
+
+// each part of `a` must match a distinct part of `b` regardless of order. Within
+// the tolerance a part can match several others so the parts are assigned with
+// augmenting paths rather than greedily.
+fn parts_equal<T>(a: &[T], b: &[T], equals: impl Fn(&T, &T) -> bool) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    // parts in the same order are the common case
+    if a.iter().zip(b.iter()).all(|(ai, bi)| equals(ai, bi)) {
+        return true;
+    }
+
+    let candidates = a
+        .iter()
+        .map(|ai| (0..b.len()).filter(|&j| equals(ai, &b[j])).collect())
+        .collect::<Vec<Vec<usize>>>();
+
+    let mut owner = vec![None; b.len()];
+
+    (0..a.len()).all(|i| assign_part(i, &candidates, &mut owner, &mut vec![false; b.len()]))
+}
+
+// assigns part `i` of `a` to a part of `b`, moving the owners of already assigned
+// parts of `b` to one of their other candidates if needed
+fn assign_part(
+    i: usize,
+    candidates: &[Vec<usize>],
+    owner: &mut [Option<usize>],
+    visited: &mut [bool],
+) -> bool {
+    for &j in &candidates[i] {
+        if visited[j] {
+            continue;
+        }
+
+        visited[j] = true;
+
+        let available = match owner[j] {
+            Some(k) => assign_part(k, candidates, owner, visited),
+            None => true,
+        };
+
+        if available {
+            owner[j] = Some(i);
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Determine whether a `Geom` has an open ring i.e. an exterior or interior ring
+/// whose first and last coordinates differ. Returns `None` for geometries that are
+/// not polygons or multipolygons.
+pub fn geom_has_open_ring(x: &Geom) -> Option<bool> {
+    let is_open = |poly: &Polygon| {
+        !poly.exterior().is_closed() || poly.interiors().iter().any(|ring| !ring.is_closed())
+    };
+
+    match &x.geom {
+        Geometry::Polygon(poly) => Some(is_open(poly)),
+        Geometry::MultiPolygon(polys) => Some(polys.iter().any(is_open)),
+        _ => None,
+    }
+}
+
+/// Audit the rings of each `Geom` in a list without modifying them. `TRUE` marks
+/// elements with an open ring, see `geom_has_open_ring()`. Non-polygon and missing
+/// geometries are `NA`.
+pub fn rsgeo_validate_rings(x: List) -> Logicals {
+    Logicals::from_values(geoms_ref_from_list(x).into_iter().map(|geom| {
+        match geom.and_then(geom_has_open_ring) {
+            Some(is_open) => Rbool::from(is_open),
+            None => Rbool::na(),
+        }
+    }))
+}
+
+/// Determine whether a `Geom` is valid using geo's validation rules. See
+/// `geom_validity_reason()` for why a geometry is invalid.
+pub fn geom_is_valid(x: &Geom) -> bool {
+    x.geom.is_valid()
+}
+
+/// Describe why a `Geom` is invalid. Returns `"Valid Geometry"` for valid
+/// geometries, otherwise every problem found separated by `"; "`.
+pub fn geom_validity_reason(x: &Geom) -> String {
+    let errors = x.geom.validation_errors();
+
+    if errors.is_empty() {
+        return String::from("Valid Geometry");
+    }
+
+    errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
+/// Determine whether each `Geom` in a list is valid. Missing geometries are `NA`.
+pub fn rsgeo_is_valid(x: List) -> Logicals {
+    Logicals::from_values(geoms_ref_from_list(x).into_iter().map(|geom| {
+        match geom {
+            Some(geom) => Rbool::from(geom_is_valid(geom)),
+            None => Rbool::na(),
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::MultiPoint;
+
+    #[test]
+    fn parts_are_assigned_not_matched_greedily() {
+        // (0.5, 0) is within 0.5 of both parts of `b` but (0, 0) only of the first
+        let a = Geom::from(MultiPoint::from(vec![(0.5, 0.0), (0.0, 0.0)]));
+        let b = Geom::from(MultiPoint::from(vec![(0.0, 0.0), (1.0, 0.0)]));
+
+        assert!(geom_equals_exact(&a, &b, 0.5));
+        assert!(geom_equals_exact(&b, &a, 0.5));
+        assert!(!geom_equals_exact(&a, &b, 0.1));
+
+        // every part must be matched by a distinct part
+        let c = Geom::from(MultiPoint::from(vec![(0.0, 0.0), (0.0, 0.0)]));
+        assert!(!geom_equals_exact(&b, &c, 0.5));
+    }
+}
+
@@ -0,0 +1,204 @@
+//! Query properties of `Geom` structs
+//!
+//! Scalar functions describe a single `Geom`. The vectorized `rsgeo_*` variants
+//! take a `List` of `Geom` pointers and return `NA` for missing geometries.
+use extendr_api::prelude::*;
+use geo_types::{Coord, Geometry, Polygon};
+use crate::{Geom, geoms_ref_from_list, tosf::from_linestring};
+
+/// Returns the OGC geometry type code of a `Geom` as used in WKB headers:
+/// 1 `POINT`, 2 `LINESTRING`, 3 `POLYGON`, 4 `MULTIPOINT`, 5 `MULTILINESTRING`,
+/// 6 `MULTIPOLYGON`, and 7 `GEOMETRYCOLLECTION`. `Line`s are `LINESTRING`s and
+/// `Rect`s and `Triangle`s are `POLYGON`s.
+pub fn geom_type_code(x: &Geom) -> i32 {
+    match &x.geom {
+        Geometry::Point(_) => 1,
+        Geometry::Line(_) | Geometry::LineString(_) => 2,
+        Geometry::Polygon(_) | Geometry::Rect(_) | Geometry::Triangle(_) => 3,
+        Geometry::MultiPoint(_) => 4,
+        Geometry::MultiLineString(_) => 5,
+        Geometry::MultiPolygon(_) => 6,
+        Geometry::GeometryCollection(_) => 7,
+    }
+}
+
+/// Returns the OGC type code of each `Geom` in a list as an integer vector.
+pub fn rsgeo_type_code(x: List) -> Robj {
+    geoms_ref_from_list(x)
+        .into_iter()
+        .map(|geom| geom.map(geom_type_code))
+        .collect::<Vec<Option<i32>>>()
+        .into()
+}
+
+/// Returns the number of interior rings of a `Geom`. A polygon returns a single
+/// count and a multipolygon returns the count for each of its polygons. Other
+/// geometries return `NA`.
+pub fn geom_n_interior_rings(x: &Geom) -> Robj {
+    let counts: Vec<Rint> = match &x.geom {
+        Geometry::Polygon(poly) => vec![Rint::from(poly.interiors().len() as i32)],
+        Geometry::MultiPolygon(polys) => polys
+            .iter()
+            .map(|poly| Rint::from(poly.interiors().len() as i32))
+            .collect(),
+        _ => vec![Rint::na()],
+    };
+
+    Integers::from_values(counts).into_robj()
+}
+
+/// Returns the `i`th interior ring of a polygon as a `LINESTRING` sfg. `i` is
+/// 1-based. Errors if `x` is not a polygon or has fewer than `i` interior rings.
+pub fn geom_interior_ring(x: &Geom, i: usize) -> Result<Robj> {
+    let poly = match &x.geom {
+        Geometry::Polygon(poly) => poly,
+        _ => return Err(Error::Other("`x` must be a polygon".to_string())),
+    };
+
+    let n = poly.interiors().len();
+
+    if i == 0 || i > n {
+        return Err(Error::Other(format!(
+            "`i` must be between 1 and {n}, the number of interior rings. Found {i}."
+        )));
+    }
+
+    Ok(from_linestring(poly.interiors()[i - 1].clone()))
+}
+
+/// Extract the coordinates of every `Geom` in a list as a named list of columns
+/// `feature_id`, `part_id`, `polygon_id`, `ring_id`, `x`, and `y` which can be
+/// passed to `as.data.frame()`. `feature_id` is the position of the geometry in `x`.
+/// `part_id` numbers the parts of each geometry from 1: each point of a multipoint,
+/// each linestring of a multilinestring, and each ring of a polygon or multipolygon.
+/// `polygon_id` numbers the polygons of each geometry from 1 and `ring_id` the rings
+/// of each polygon from 0, the exterior ring, as `fromdf::multipolygons_from_df()`
+/// expects. They are `NA` for coordinates that are not part of a polygon. Missing
+/// geometries contribute no rows.
+pub fn rsgeo_coordinates(x: List) -> Robj {
+    let mut feature_id: Vec<i32> = Vec::new();
+    let mut part_id: Vec<i32> = Vec::new();
+    let mut polygon_id: Vec<Rint> = Vec::new();
+    let mut ring_id: Vec<Rint> = Vec::new();
+    let mut xs: Vec<f64> = Vec::new();
+    let mut ys: Vec<f64> = Vec::new();
+
+    for (i, geom) in geoms_ref_from_list(x).into_iter().enumerate() {
+        let geom = match geom {
+            Some(geom) => geom,
+            None => continue,
+        };
+
+        let (mut part, mut polygon) = (0, 0);
+        let mut push_part = |coords: &[Coord], ring: Option<usize>| {
+            part += 1;
+
+            // the exterior ring starts a new polygon
+            if ring == Some(0) {
+                polygon += 1;
+            }
+
+            let (poly, ring) = match ring {
+                Some(ring) => (Rint::from(polygon), Rint::from(ring as i32)),
+                None => (Rint::na(), Rint::na()),
+            };
+
+            for crd in coords {
+                feature_id.push(i as i32 + 1);
+                part_id.push(part);
+                polygon_id.push(poly);
+                ring_id.push(ring);
+                xs.push(crd.x);
+                ys.push(crd.y);
+            }
+        };
+
+        for_each_part(&geom.geom, &mut push_part);
+    }
+
+    List::from_names_and_values(
+        ["feature_id", "part_id", "polygon_id", "ring_id", "x", "y"],
+        [
+            feature_id.into_robj(),
+            part_id.into_robj(),
+            Integers::from_values(polygon_id).into_robj(),
+            Integers::from_values(ring_id).into_robj(),
+            xs.into_robj(),
+            ys.into_robj(),
+        ],
+    )
+    .unwrap()
+    .into_robj()
+}
+
+// calls `f` with the coordinates of each part of a geometry and, for polygon
+// rings, the index of the ring within its polygon
+fn for_each_part(x: &Geometry, f: &mut dyn FnMut(&[Coord], Option<usize>)) {
+    match x {
+        Geometry::Point(x) => f(std::slice::from_ref(&x.0), None),
+        Geometry::Line(x) => f(&[x.start, x.end], None),
+        Geometry::MultiPoint(x) => x.iter().for_each(|pnt| f(std::slice::from_ref(&pnt.0), None)),
+        Geometry::LineString(x) => f(&x.0, None),
+        Geometry::MultiLineString(x) => x.iter().for_each(|lns| f(&lns.0, None)),
+        Geometry::Polygon(x) => polygon_parts(x, f),
+        Geometry::MultiPolygon(x) => x.iter().for_each(|poly| polygon_parts(poly, f)),
+        Geometry::Rect(x) => polygon_parts(&x.to_polygon(), f),
+        Geometry::Triangle(x) => polygon_parts(&x.to_polygon(), f),
+        Geometry::GeometryCollection(x) => x.iter().for_each(|geom| for_each_part(geom, f)),
+    }
+}
+
+fn polygon_parts(x: &Polygon, f: &mut dyn FnMut(&[Coord], Option<usize>)) {
+    f(&x.exterior().0, Some(0));
+    x.interiors().iter().enumerate().for_each(|(i, ring)| f(&ring.0, Some(i + 1)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::{Line, LineString, MultiPoint, MultiPolygon, Point, Rect};
+
+    #[test]
+    fn type_codes_follow_ogc() {
+        let code = |x: Geometry| geom_type_code(&Geom::from(x));
+
+        assert_eq!(code(Point::new(0.0, 0.0).into()), 1);
+        assert_eq!(code(LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]).into()), 2);
+        assert_eq!(code(Line::new((0.0, 0.0), (1.0, 1.0)).into()), 2);
+        assert_eq!(code(Rect::new((0.0, 0.0), (1.0, 1.0)).into()), 3);
+        assert_eq!(code(MultiPoint::from(vec![(0.0, 0.0)]).into()), 4);
+        assert_eq!(code(Geometry::GeometryCollection(Default::default())), 7);
+    }
+
+    #[test]
+    fn coordinates_number_polygons_and_rings() {
+        test! {
+            let ring = |x: f64| LineString::from(vec![(x, 0.0), (x + 1.0, 0.0), (x, 1.0), (x, 0.0)]);
+            let mpoly = MultiPolygon::new(vec![
+                Polygon::new(ring(0.0), vec![ring(0.1)]),
+                Polygon::new(ring(5.0), vec![]),
+            ]);
+
+            let x = List::from_values([
+                Geom::from(Point::new(1.0, 2.0)).into_robj(),
+                Robj::from(NULL),
+                Geom::from(mpoly).into_robj(),
+            ]);
+
+            let res = List::try_from(rsgeo_coordinates(x)).unwrap();
+            let col = |i: usize| res.elt(i).unwrap();
+
+            assert_eq!(
+                res.names().unwrap().collect::<Vec<&str>>(),
+                vec!["feature_id", "part_id", "polygon_id", "ring_id", "x", "y"]
+            );
+
+            let na = i32::MIN;
+            assert_eq!(col(0).as_integer_slice().unwrap(), &[1, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3]);
+            assert_eq!(col(1).as_integer_slice().unwrap(), &[1, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3]);
+            assert_eq!(col(2).as_integer_slice().unwrap(), &[na, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2]);
+            assert_eq!(col(3).as_integer_slice().unwrap(), &[na, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0]);
+            assert_eq!(col(4).as_real_slice().unwrap()[..2], [1.0, 0.0]);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Interoperability with the s2 R package
+//!
+//! s2 geographies are spherical while `Geom`s are planar. Geographies are exchanged
+//! as WKB: `s2::s2_as_binary()` creates a `wk_wkb` vector that is read by
+//! `wk::wk_to_geoms()`, and the `wk_wkb` vector from `wk::geoms_to_wk_wkb()` is
+//! read by `s2::as_s2_geography()`. Coordinates must be longitude / latitude.
+//! s2 has no WKB dialect of its own so there are no s2 specific functions.
+//!
+//! Some geographies have no planar equivalent:
+//!
+//! - Empty geographies are exported by s2 as empty geometries and become empty `Geom`s.
+//! - The full polygon, which covers the whole sphere, cannot be represented in WKB.
+//!   Invalid WKB results in an error with the element position rather than a panic.
+//! - Polygons crossing the antimeridian are converted as is. Their edges are not
+//!   split at ±180 so the planar `Geom` spans the "wrong" way around the globe.
+//!   This is documented rather than silently fixed.
+
+#[cfg(test)]
+mod tests {
+    use extendr_api::prelude::*;
+    use geo::{Area, BoundingRect, HasDimensions};
+    use geo_types::{Polygon, Rect};
+    use crate::{wk::{geoms_to_wk_wkb, wk_to_geoms}, wkb::geometry_to_wkb};
+
+    // the `wk_wkb` of `s2::s2_as_binary()` for a list of geometries
+    fn s2_wkb(x: Vec<Vec<u8>>) -> Robj {
+        x.into_iter()
+            .map(|bytes| Raw::from_bytes(&bytes).into_robj())
+            .collect::<List>()
+            .into_robj()
+            .set_class(["wk_wkb", "wk_vctr"])
+            .unwrap()
+    }
+
+    #[test]
+    fn antimeridian_polygons_are_not_split() {
+        test! {
+            // `s2::s2_as_binary(s2::as_s2_geography("POLYGON ((179 0, -179 0, -179 1, 179 1, 179 0))"))`
+            let mut fixture = vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00];
+            for (x, y) in [(179.0, 0.0), (-179.0, 0.0), (-179.0, 1.0), (179.0, 1.0), (179.0, 0.0)] {
+                fixture.extend(f64::to_le_bytes(x));
+                fixture.extend(f64::to_le_bytes(y));
+            }
+
+            let geoms = wk_to_geoms(s2_wkb(vec![fixture.clone()])).unwrap();
+            let poly: Polygon = geoms[0].clone().unwrap().geom.try_into().unwrap();
+
+            // 2 degrees wide on the sphere but 358 degrees wide in the plane
+            let bbox = poly.bounding_rect().unwrap();
+            assert_eq!(bbox, Rect::new((-179.0, 0.0), (179.0, 1.0)));
+            assert_eq!(poly.unsigned_area(), 358.0);
+
+            // and it is written back unchanged
+            let wkb = List::try_from(geoms_to_wk_wkb(geoms)).unwrap();
+            assert_eq!(wkb.elt(0).unwrap().as_raw_slice(), Some(&fixture[..]));
+        }
+    }
+
+    #[test]
+    fn empty_and_invalid_geographies() {
+        test! {
+            // `s2::s2_as_binary(s2::as_s2_geography("POLYGON EMPTY"))`
+            let empty = vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+            let geoms = wk_to_geoms(s2_wkb(vec![empty.clone()])).unwrap();
+            assert!(geoms[0].clone().unwrap().geom.is_empty());
+            assert_eq!(geometry_to_wkb(&geoms[0].clone().unwrap().geom), empty);
+
+            // a truncated buffer errors with its position
+            let err = wk_to_geoms(s2_wkb(vec![empty, vec![0x01, 0x03, 0x00]])).unwrap_err();
+            assert!(err.to_string().contains("element 2"));
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Read shapefiles
+//!
+//! Available with the `shapefile` feature. Shapes are read using the
+//! [shapefile](https://docs.rs/shapefile) crate and the attribute table is read
+//! from the accompanying `.dbf` file. Null shapes are missing geometries. Z and M
+//! values are dropped.
+use std::path::Path;
+
+use extendr_api::prelude::*;
+use geo_types::Geometry;
+use shapefile::dbase::{self, FieldType, FieldValue, Record};
+use shapefile::{Shape, ShapeReader};
+use crate::{Geom, vctrs::geoms_to_rsgeo};
+
+/// Read a shapefile into a named list of the attribute columns in the `.dbf` file
+/// followed by a `geometry` column containing an rsgeo vctr. The list can be
+/// passed to `data.frame()` or `sf::st_as_sf()`. See `read_shp_geoms()`.
+pub fn read_shp(path: &str) -> Result<Robj> {
+    let (geoms, attributes) = read_shp_geoms(path)?;
+
+    let (mut names, mut values): (Vec<String>, Vec<Robj>) = attributes
+        .iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .unzip();
+
+    names.push(String::from("geometry"));
+    values.push(geoms_to_rsgeo(geoms));
+
+    Ok(List::from_names_and_values(names, values)?.into_robj())
+}
+
+/// Read a shapefile into a `Vec<Option<Geom>>` and a named list of the attribute
+/// columns in the `.dbf` file. Polylines are MultiLineStrings. Polygon rings are
+/// grouped into polygons using their orientation and a single polygon is returned
+/// as a Polygon rather than a MultiPolygon. MultiPatch shapes are not supported.
+pub fn read_shp_geoms(path: &str) -> Result<(Vec<Option<Geom>>, List)> {
+    let shapes = ShapeReader::from_path(path)
+        .and_then(|mut reader| reader.read())
+        .map_err(|e| Error::Other(format!("Failed to read `{path}`\n{e}")))?;
+
+    let geoms = shapes
+        .into_iter()
+        .enumerate()
+        .map(|(i, shape)| {
+            shape_to_geom(shape)
+                .map_err(|e| Error::Other(format!("Failed to convert shape {}\n{e}", i + 1)))
+        })
+        .collect::<Result<Vec<Option<Geom>>>>()?;
+
+    let dbf = Path::new(path).with_extension("dbf");
+    let attributes = read_dbf(&dbf)
+        .map_err(|e| Error::Other(format!("Failed to read `{}`\n{e}", dbf.display())))?;
+
+    Ok((geoms, attributes))
+}
+
+fn shape_to_geom(x: Shape) -> std::result::Result<Option<Geom>, String> {
+    match x {
+        Shape::NullShape => return Ok(None),
+        Shape::Multipatch(_) => {
+            return Err(format!("Unsupported shape type `{}`", x.shapetype()))
+        }
+        _ => (),
+    }
+
+    let geom = Geometry::<f64>::try_from(x).map_err(|e| e.to_string())?;
+
+    let geom = match geom {
+        Geometry::MultiPolygon(mut polys) if polys.0.len() == 1 => {
+            Geometry::Polygon(polys.0.remove(0))
+        }
+        geom => geom,
+    };
+
+    Ok(Some(Geom::from(geom)))
+}
+
+fn read_dbf(path: &Path) -> std::result::Result<List, dbase::Error> {
+    let mut reader = dbase::Reader::from_path(path)?;
+
+    let fields = reader
+        .fields()
+        .iter()
+        .map(|field| (field.name().to_string(), field.field_type()))
+        .collect::<Vec<(String, FieldType)>>();
+
+    let records = reader.read()?;
+
+    let columns = fields
+        .iter()
+        .map(|(name, field_type)| field_column(&records, name, *field_type))
+        .collect::<Vec<Robj>>();
+
+    Ok(List::from_names_and_values(fields.iter().map(|(name, _)| name), columns).unwrap())
+}
+
+// numeric fields are doubles, logical fields are logicals, everything else is a string
+fn field_column(records: &[Record], name: &str, field_type: FieldType) -> Robj {
+    let values = records.iter().map(|record| record.get(name));
+
+    match field_type {
+        FieldType::Numeric
+        | FieldType::Float
+        | FieldType::Integer
+        | FieldType::Currency
+        | FieldType::Double => Doubles::from_values(values.map(|v| {
+            let v = match v {
+                Some(FieldValue::Numeric(v)) => *v,
+                Some(FieldValue::Float(v)) => v.map(f64::from),
+                Some(FieldValue::Integer(v)) => Some(f64::from(*v)),
+                Some(FieldValue::Currency(v)) | Some(FieldValue::Double(v)) => Some(*v),
+                _ => None,
+            };
+
+            v.map(Rfloat::from).unwrap_or(Rfloat::na())
+        }))
+        .into_robj(),
+        FieldType::Logical => Logicals::from_values(values.map(|v| match v {
+            Some(FieldValue::Logical(Some(v))) => Rbool::from(*v),
+            _ => Rbool::na(),
+        }))
+        .into_robj(),
+        _ => values
+            .map(|v| match v {
+                Some(FieldValue::Character(v)) => v.clone(),
+                Some(FieldValue::Memo(v)) => Some(v.clone()),
+                Some(FieldValue::Date(Some(d))) => {
+                    Some(format!("{:04}-{:02}-{:02}", d.year(), d.month(), d.day()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<Option<String>>>()
+            .into_robj(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::{Coord, MultiPolygon, Polygon};
+
+    // a square with a hole, a null shape, and a polygon with two parts
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/polygons.shp");
+
+    #[test]
+    fn read_bundled_fixture() {
+        test! {
+            let (geoms, attributes) = read_shp_geoms(FIXTURE).unwrap();
+
+            assert_eq!(geoms.len(), 3);
+            assert!(geoms[1].is_none());
+
+            let poly: Polygon = geoms[0].clone().unwrap().geom.try_into().unwrap();
+            assert_eq!(poly.interiors().len(), 1);
+            assert!(poly.exterior().0.contains(&Coord { x: 10.0, y: 10.0 }));
+
+            let mpoly: MultiPolygon = geoms[2].clone().unwrap().geom.try_into().unwrap();
+            assert_eq!(mpoly.0.len(), 2);
+
+            assert_eq!(attributes.names().unwrap().collect::<Vec<&str>>(), vec!["name", "value"]);
+            let value = attributes.elt(1).unwrap();
+            assert_eq!(value.as_real_slice(), Some(&[1.5, 2.0, 3.25][..]));
+
+            let res = List::try_from(read_shp(FIXTURE).unwrap()).unwrap();
+            assert_eq!(res.names().unwrap().collect::<Vec<&str>>(), vec!["name", "value", "geometry"]);
+
+            let geometry = res.elt(2).unwrap();
+            assert_eq!(geometry.len(), 3);
+            assert!(geometry.inherits("rsgeo"));
+
+            let err = read_shp("does-not-exist.shp").unwrap_err().to_string();
+            assert!(err.contains("does-not-exist.shp"));
+        }
+    }
+}
+
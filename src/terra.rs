@@ -0,0 +1,332 @@
+//! Interoperability with terra geometry matrices
+//!
+//! `terra::geom()` returns the geometries of a SpatVector as a five column matrix
+//! with columns `geom`, `part`, `x`, `y`, and `hole`. Each row is a coordinate.
+//! Rows with the same `geom` id form a geometry and rows with the same `part` id
+//! form one of its parts. For polygons, `hole = 1` marks coordinates that belong
+//! to an interior ring of the part.
+//!
+//! Rows must be sorted by `geom` and then `part` and ids must be positive integers.
+//! Unsorted, missing, or fractional ids are rejected with the offending row. `geom` ids do not need to be contiguous: ids that do not
+//! appear in the matrix are missing geometries. At most `MAX_MISSING` ids can be
+//! skipped so that a single large id cannot allocate a huge result.
+use extendr_api::prelude::*;
+use geo_types::{
+    Coord, Geometry, LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon
+};
+use crate::{Geom, tosf::new_matrix};
+
+/// The most `geom` ids that can be absent from a terra geometry matrix.
+pub const MAX_MISSING: usize = 1 << 20;
+
+/// Convert a terra geometry matrix into a `Vec<Option<Geom>>`. `geom_type` is
+/// the SpatVector geometry type: `"points"`, `"lines"`, or `"polygons"`.
+pub fn geom_matrix_to_geoms(x: RMatrix<f64>, geom_type: &str) -> Result<Vec<Option<Geom>>> {
+    let nrow = x.nrows();
+    let ncol = x.ncols();
+
+    if ncol < 4 || (geom_type == "polygons" && ncol < 5) {
+        return Err(Error::Other(format!(
+            "Expected columns `geom`, `part`, `x`, `y`, and `hole`. Found {ncol} columns."
+        )));
+    }
+
+    let data = x.data();
+    let col = |j: usize| &data[j * nrow..(j + 1) * nrow];
+    let (geom_id, part_id, xs, ys) = (col(0), col(1), col(2), col(3));
+    let hole = if ncol >= 5 { Some(col(4)) } else { None };
+
+    // ids are stored as doubles so they must be checked to be positive integers
+    for i in 0..nrow {
+        for (name, id) in [("geom", geom_id[i]), ("part", part_id[i])] {
+            if !id.is_finite() || id.fract() != 0.0 || id < 1.0 {
+                return Err(Error::Other(format!(
+                    "`{name}` ids must be positive integers. Found {id} at row {}.",
+                    i + 1
+                )));
+            }
+        }
+    }
+
+    // validate the sort order
+    for i in 1..nrow {
+        let unsorted = geom_id[i] < geom_id[i - 1] ||
+            (geom_id[i] == geom_id[i - 1] && part_id[i] < part_id[i - 1]);
+
+        if unsorted {
+            return Err(Error::Other(format!(
+                "`geom` and `part` ids must be sorted. Found unsorted ids at row {}.",
+                i + 1
+            )));
+        }
+    }
+
+    // rows are sorted so the last id is the largest and each run is a geometry
+    let n_geoms = geom_id.last().map(|id| *id as usize).unwrap_or(0);
+    let n_present = (0..nrow).filter(|&i| i == 0 || geom_id[i] != geom_id[i - 1]).count();
+
+    if n_geoms - n_present > MAX_MISSING {
+        return Err(Error::Other(format!(
+            "`geom` ids skip {} geometries. At most {MAX_MISSING} can be missing.",
+            n_geoms - n_present
+        )));
+    }
+
+    let mut res: Vec<Option<Geom>> = vec![None; n_geoms];
+
+    // iterate over runs of rows that share a geom id
+    let mut start = 0;
+    while start < nrow {
+        let id = geom_id[start];
+        let end = (start..nrow).find(|&i| geom_id[i] != id).unwrap_or(nrow);
+
+        // the parts of the geometry as (start, end) row ranges
+        let mut parts: Vec<(usize, usize)> = Vec::new();
+        let mut part_start = start;
+        for i in (start + 1)..=end {
+            if i == end || part_id[i] != part_id[part_start] {
+                parts.push((part_start, i));
+                part_start = i;
+            }
+        }
+
+        let coords = |from: usize, to: usize| -> Vec<Coord> {
+            (from..to).map(|i| Coord { x: xs[i], y: ys[i] }).collect()
+        };
+
+        let geom: Geometry = match geom_type {
+            "points" => {
+                let pnts = coords(start, end).into_iter().map(Point::from).collect::<Vec<Point>>();
+                if pnts.len() == 1 {
+                    pnts[0].into()
+                } else {
+                    MultiPoint::new(pnts).into()
+                }
+            }
+            "lines" => {
+                let mut lns = parts
+                    .iter()
+                    .map(|&(from, to)| LineString::new(coords(from, to)))
+                    .collect::<Vec<LineString>>();
+                if lns.len() == 1 {
+                    lns.remove(0).into()
+                } else {
+                    MultiLineString::new(lns).into()
+                }
+            }
+            "polygons" => {
+                let hole = hole.unwrap();
+                let mut polys = parts
+                    .iter()
+                    .map(|&(from, to)| terra_polygon(&coords(from, to), &hole[from..to]))
+                    .collect::<Vec<Polygon>>();
+                if polys.len() == 1 {
+                    polys.remove(0).into()
+                } else {
+                    MultiPolygon::new(polys).into()
+                }
+            }
+            _ => {
+                return Err(Error::Other(format!(
+                    "`geom_type` must be one of \"points\", \"lines\", or \"polygons\" not \"{geom_type}\""
+                )))
+            }
+        };
+
+        res[id as usize - 1] = Some(Geom::from(geom));
+        start = end;
+    }
+
+    Ok(res)
+}
+
+// a new ring starts when the hole flag changes or when the current ring is closed
+fn terra_polygon(coords: &[Coord], hole: &[f64]) -> Polygon {
+    let mut rings: Vec<Vec<Coord>> = Vec::new();
+    let mut current: Vec<Coord> = Vec::new();
+
+    for (i, crd) in coords.iter().enumerate() {
+        let flag_changed = i > 0 && hole[i] != hole[i - 1];
+        let closed = current.len() >= 4 && current.first() == current.last();
+
+        if !current.is_empty() && (flag_changed || closed) {
+            rings.push(std::mem::take(&mut current));
+        }
+
+        current.push(*crd);
+    }
+
+    if !current.is_empty() {
+        rings.push(current);
+    }
+
+    let mut rings = rings.into_iter().map(LineString::new);
+    let exterior = rings.next().unwrap_or_else(|| LineString::new(vec![]));
+    Polygon::new(exterior, rings.collect())
+}
+
+/// Convert a slice of `Option<Geom>` into a terra geometry matrix. The `geom` id is
+/// the position of the geometry so missing geometries are skipped but keep their id.
+/// A `Line` is written as a linestring and `Rect`s and `Triangle`s as polygons.
+/// terra has no geometry collections so they are an error.
+pub fn geoms_to_geom_matrix(x: &[Option<Geom>]) -> Result<Robj> {
+    let mut cols: [Vec<f64>; 5] = Default::default();
+
+    let mut push = |geom: usize, part: usize, crd: &Coord, hole: f64| {
+        cols[0].push(geom as f64);
+        cols[1].push(part as f64);
+        cols[2].push(crd.x);
+        cols[3].push(crd.y);
+        cols[4].push(hole);
+    };
+
+    for (i, geom) in x.iter().enumerate() {
+        let geom = match geom {
+            Some(geom) => &geom.geom,
+            None => continue,
+        };
+
+        let id = i + 1;
+
+        match geom {
+            Geometry::Point(pnt) => push(id, 1, &pnt.0, 0.0),
+            Geometry::MultiPoint(pnts) => {
+                pnts.iter().enumerate().for_each(|(j, pnt)| push(id, j + 1, &pnt.0, 0.0))
+            }
+            Geometry::LineString(lns) => lns.0.iter().for_each(|crd| push(id, 1, crd, 0.0)),
+            Geometry::MultiLineString(lns) => lns.iter().enumerate().for_each(|(j, lns)| {
+                lns.0.iter().for_each(|crd| push(id, j + 1, crd, 0.0))
+            }),
+            Geometry::Polygon(poly) => push_polygon(&mut push, id, 1, poly),
+            Geometry::MultiPolygon(polys) => polys
+                .iter()
+                .enumerate()
+                .for_each(|(j, poly)| push_polygon(&mut push, id, j + 1, poly)),
+            Geometry::Line(x) => [x.start, x.end].iter().for_each(|crd| push(id, 1, crd, 0.0)),
+            Geometry::Rect(x) => push_polygon(&mut push, id, 1, &x.to_polygon()),
+            Geometry::Triangle(x) => push_polygon(&mut push, id, 1, &x.to_polygon()),
+            Geometry::GeometryCollection(_) => {
+                return Err(Error::Other(format!(
+                    "Geometry {id} is a geometry collection which terra does not support"
+                )))
+            }
+        }
+    }
+
+    let nrow = cols[0].len();
+    let data = cols.concat();
+
+    let dimnames = List::from_values([
+        Robj::from(NULL),
+        Robj::from(vec!["geom", "part", "x", "y", "hole"]),
+    ]);
+
+    new_matrix(data, nrow, 5).set_attrib("dimnames", dimnames)
+}
+
+fn push_polygon(
+    push: &mut impl FnMut(usize, usize, &Coord, f64),
+    id: usize,
+    part: usize,
+    poly: &Polygon,
+) {
+    poly.exterior().0.iter().for_each(|crd| push(id, part, crd, 0.0));
+
+    for ring in poly.interiors() {
+        ring.0.iter().for_each(|crd| push(id, part, crd, 1.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::{Line, Rect};
+
+    fn matrix(rows: &[[f64; 5]]) -> RMatrix<f64> {
+        RMatrix::new_matrix(rows.len(), 5, |r, c| rows[r][c])
+    }
+
+    #[test]
+    fn multipolygon_with_holes_round_trip() {
+        test! {
+            let square = |x: f64, size: f64| {
+                LineString::from(vec![(x, 0.0), (x + size, 0.0), (x + size, size), (x, size), (x, 0.0)])
+            };
+
+            let mpoly = MultiPolygon::new(vec![
+                Polygon::new(square(0.0, 10.0), vec![square(1.0, 2.0), square(5.0, 2.0)]),
+                Polygon::new(square(20.0, 5.0), vec![square(21.0, 1.0)]),
+            ]);
+
+            let geoms = vec![Some(Geom::from(mpoly.clone())), None, Some(Geom::from(mpoly.0[1].clone()))];
+            let mat = RMatrix::<f64>::try_from(geoms_to_geom_matrix(&geoms).unwrap()).unwrap();
+            let res = geom_matrix_to_geoms(mat, "polygons").unwrap();
+
+            assert_eq!(res.len(), 3);
+            assert_eq!(res[0].clone().unwrap().geom, Geometry::from(mpoly.clone()));
+            assert!(res[1].is_none());
+            assert_eq!(res[2].clone().unwrap().geom, Geometry::from(mpoly.0[1].clone()));
+        }
+    }
+
+    #[test]
+    fn ids_must_be_positive_integers() {
+        test! {
+            let row = |geom: f64, part: f64| [geom, part, 0.0, 0.0, 0.0];
+
+            for (id, bad) in [(f64::NAN, "NaN"), (1.5, "1.5"), (0.0, "0"), (f64::INFINITY, "inf")] {
+                let x = matrix(&[row(1.0, 1.0), row(id, 1.0)]);
+                let err = geom_matrix_to_geoms(x, "points").unwrap_err().to_string();
+                assert!(err.contains(&format!("`geom` ids must be positive integers. Found {bad} at row 2")), "{err}");
+            }
+
+            let x = matrix(&[row(1.0, f64::NAN)]);
+            let err = geom_matrix_to_geoms(x, "points").unwrap_err().to_string();
+            assert!(err.contains("`part` ids") && err.contains("row 1"));
+
+            let x = matrix(&[row(2.0, 1.0), row(1.0, 1.0)]);
+            assert!(geom_matrix_to_geoms(x, "points").is_err());
+        }
+    }
+
+    #[test]
+    fn missing_ids_are_capped() {
+        test! {
+            let row = |geom: f64| [geom, 1.0, 0.0, 0.0, 0.0];
+
+            let res = geom_matrix_to_geoms(matrix(&[row(1.0), row(3.0)]), "points").unwrap();
+            assert_eq!(res.len(), 3);
+            assert!(res[1].is_none());
+
+            let cap = (MAX_MISSING + 2) as f64;
+            assert_eq!(geom_matrix_to_geoms(matrix(&[row(1.0), row(cap)]), "points").unwrap().len(), MAX_MISSING + 2);
+
+            let err = geom_matrix_to_geoms(matrix(&[row(1.0), row(cap + 1.0)]), "points").unwrap_err().to_string();
+            assert!(err.contains(&format!("skip {} geometries", MAX_MISSING + 1)), "{err}");
+
+            let err = geom_matrix_to_geoms(matrix(&[row(1e15)]), "points").unwrap_err().to_string();
+            assert!(err.contains("At most"), "{err}");
+        }
+    }
+
+    #[test]
+    fn primitives_are_written_and_collections_rejected() {
+        test! {
+            let line = Line::new((0.0, 0.0), (1.0, 1.0));
+            let rect = Rect::new((0.0, 0.0), (1.0, 1.0));
+            let geoms = vec![Some(Geom::from(line)), Some(Geom::from(rect))];
+
+            let mat = geoms_to_geom_matrix(&geoms).unwrap();
+            assert_eq!(RMatrix::<f64>::try_from(mat.clone()).unwrap().nrows(), 2 + 5);
+
+            let lines = geom_matrix_to_geoms(RMatrix::try_from(mat.clone()).unwrap(), "lines").unwrap();
+            assert_eq!(lines[0].clone().unwrap().geom, LineString::new(vec![line.start, line.end]).into());
+            let polys = geom_matrix_to_geoms(RMatrix::try_from(mat).unwrap(), "polygons").unwrap();
+            assert_eq!(polys[1].clone().unwrap().geom, rect.to_polygon().into());
+
+            let gc = Geometry::GeometryCollection(Default::default());
+            let err = geoms_to_geom_matrix(&[None, Some(Geom::from(gc))]).unwrap_err().to_string();
+            assert!(err.contains("Geometry 2 is a geometry collection"), "{err}");
+        }
+    }
+}
@@ -8,21 +8,28 @@
 use extendr_api::prelude::*;
 use extendr_api::Robj;
 use geo_types::*;
-use crate::Geom;
+use geo::HasDimensions;
+use crate::{Geom, geoms_from_list, fromsf::apply_coord_hook, crs::Crs, measures::union_bbox, meta::{SfMeta, SfcMeta}};
 
 /// A general purpose function that matches on the `Geometry` enum to convert into the 
-/// appropriate sfg object type. If the Geom cannot be matched (e.g. Line or Triangle), 
-/// it will return a `NULL` Robj.
+/// appropriate sfg object type. Every variant is converted: a `Line` becomes a
+/// `LINESTRING` and `Rect` and `Triangle` become a `POLYGON`.
 pub fn to_sfg(x: Geom) -> Robj {
-    let x = x.geom;
+    geometry_to_sfg(x.geom)
+}
+
+fn geometry_to_sfg(x: Geometry) -> Robj {
     match x {
         Geometry::Point(x) => from_point(x),
+        Geometry::Line(x) => from_linestring(LineString::new(vec![x.start, x.end])),
         Geometry::MultiPoint(x) => from_multipoint(x),
         Geometry::LineString(x) => from_linestring(x),
         Geometry::MultiLineString(x) => from_multilinestring(x),
         Geometry::Polygon(x) => from_polygon(x),
         Geometry::MultiPolygon(x) => from_multipolygon(x),
-        _ => Robj::from(NULL),
+        Geometry::Rect(x) => from_polygon(x.to_polygon()),
+        Geometry::Triangle(x) => from_polygon(x.to_polygon()),
+        Geometry::GeometryCollection(x) => from_geometrycollection(x),
     }
 }
 
@@ -48,25 +55,237 @@ pub fn geoms_to_sfc(x: Vec<Option<Geom>>) -> List {
 
 }
 
-/// Utility function to identify the class of an sfc object . 
-pub fn determine_sfc_class(x: &Vec<Option<Geom>>) -> String {
-    let mut result = String::new();
-    for geom in x {
-        match geom {
-            Some(geom) => {
-                let fstr = format!("{:?}", geom.geom);
-                let cls = fstr.splitn(2, '(').next().unwrap().to_string();
-                if result.is_empty() {
-                    result = cls;
-                } else if result != cls  {
-                    result = "GEOMETRYCOLLECTION".to_string();
-                    break;
+/// Like `geoms_to_sfc()` but borrows `x` and writes the sfg objects into `out`, a
+/// list that has already been allocated, so the same list can be reused across
+/// calls. `out` must be the same length as `x`. Every element of `out` is
+/// overwritten: missing geometries are set to `NULL` so a reused list matches the
+/// result of `geoms_to_sfc()` exactly.
+pub fn geoms_to_sfc_into(x: &[Option<Geom>], out: &mut List) -> Result<()> {
+    if out.len() != x.len() {
+        return Err(Error::Other(format!(
+            "`out` must be the same length as `x`. Found {} and {}.",
+            out.len(),
+            x.len()
+        )));
+    }
+
+    for (i, geom) in x.iter().enumerate() {
+        let sfg = match geom {
+            Some(geo) => geometry_to_sfg(geo.geom.clone()),
+            None => Robj::from(NULL),
+        };
+
+        out.set_elt(i, sfg)?;
+    }
+
+    Ok(())
+}
+
+/// Like `geoms_to_sfc()` but applies `hook` to the coordinates of each geometry
+/// before it is converted. See `fromsf::apply_coord_hook()`.
+pub fn geoms_to_sfc_with_hook(x: Vec<Option<Geom>>, hook: &Function) -> Result<List> {
+    let mut res = List::new(x.len());
+
+    for (i, geom) in x.into_iter().enumerate() {
+        if let Some(mut geom) = geom {
+            apply_coord_hook(&mut geom, hook, i)?;
+            res.set_elt(i, to_sfg(geom))?;
+        }
+    }
+
+    Ok(res)
+}
+
+/// Converts an rsgeo vctr into a list of sfg objects. The names of `x`, if any, are
+/// kept so that a round trip from `fromsf::sfc_to_rsgeo()` preserves them.
+pub fn rsgeo_to_sfc(x: List) -> Robj {
+    let names = x.get_attrib("names");
+    let res = geoms_to_sfc(geoms_from_list(x)).into_robj();
+
+    match names {
+        Some(names) if !names.is_null() => res.set_attrib("names", names).unwrap(),
+        _ => res,
+    }
+}
+
+/// Takes a `Vec<Option<Geom>>` and creates a list of sfg objects with an sf-compatible
+/// `crs` attribute. The `crs` is a list with elements `input` and `wkt` as created by
+/// `sf::st_crs()`. When an EPSG code is provided the `input` is `"EPSG:{code}"`,
+/// otherwise the `wkt` string is used as the `input`. When both are `None` the
+/// `NA` crs is set, matching sf's default.
+///
+/// Note that sf resolves the `wkt` of an EPSG code using PROJ which is not available
+/// here. If only `epsg` is provided the `wkt` element is `NA`.
+pub fn geoms_to_sfc_crs(x: Vec<Option<Geom>>, epsg: Option<i32>, wkt: Option<&str>) -> Robj {
+    geoms_to_sfc_precision(x, epsg, wkt, 0.0)
+}
+
+/// Like `geoms_to_sfc_crs()` but additionally sets the `precision` and `n_empty`
+/// attributes of an sfc object. `n_empty` is counted while the sfg objects are
+/// created; both missing and empty geometries count towards it.
+/// sf's default `precision` is `0`.
+pub fn geoms_to_sfc_precision(
+    x: Vec<Option<Geom>>,
+    epsg: Option<i32>,
+    wkt: Option<&str>,
+    precision: f64,
+) -> Robj {
+    sfc_with_attributes(x, Crs::new(epsg, wkt), precision)
+}
+
+// sets the crs, precision, and n_empty attributes while creating the sfg objects
+fn sfc_with_attributes(x: Vec<Option<Geom>>, crs: Crs, precision: f64) -> Robj {
+    let mut n_empty = 0;
+
+    let res = x
+        .into_iter()
+        .map(|geom| {
+            match geom {
+                Some(geo) => {
+                    if geo.geom.is_empty() {
+                        n_empty += 1;
+                    }
+                    to_sfg(geo)
+                },
+                None => {
+                    n_empty += 1;
+                    Robj::from(NULL)
                 }
-            },
-            None => continue
+            }
+        }).collect::<List>();
+
+    res
+        .into_robj()
+        .set_attrib("crs", crs)
+        .unwrap()
+        .set_attrib("precision", precision)
+        .unwrap()
+        .set_attrib("n_empty", n_empty)
+        .unwrap()
+}
+
+/// Creates an sfc object from a `Vec<Option<Geom>>` and the attributes captured in an
+/// `SfcMeta`. The crs, precision, and names are reapplied and the `bbox`, `n_empty`,
+/// and class are computed from the geometries. The names are only reapplied if the
+/// number of geometries is unchanged. If there are no geometries the class captured
+/// in the `SfcMeta` is used.
+pub fn geoms_to_sfc_with_meta(x: Vec<Option<Geom>>, meta: &SfcMeta) -> Robj {
+    let n = x.len();
+    let bbox = geoms_bbox(&x);
+    let cls = determine_sfc_class(&x);
+
+    let cls = match (cls.as_str(), &meta.class) {
+        ("", Some(declared)) => declared.clone(),
+        ("", None) => String::from("sfc_GEOMETRY"),
+        (cls, _) => format!("sfc_{cls}"),
+    };
+
+    let crs = meta.crs.clone().unwrap_or(Crs { input: None, wkt: None });
+
+    let mut res = sfc_with_attributes(x, crs, meta.precision)
+        .set_attrib("bbox", bbox)
+        .unwrap()
+        .set_class([cls.as_str(), "sfc"])
+        .unwrap();
+
+    if let Some(names) = meta.names_for(n) {
+        res = res.set_attrib("names", names.to_vec()).unwrap();
+    }
+
+    res
+}
+
+/// Creates an sf data.frame from a `Vec<Option<Geom>>`, its attribute columns, and
+/// the attributes captured in an `SfMeta`. The geometry column is created with
+/// `geoms_to_sfc_with_meta()` and named after the captured `sf_column`. The `agr`
+/// attribute is reapplied for the columns of `attributes`. Errors if a column's
+/// length differs from the number of geometries.
+pub fn geoms_to_sf(x: Vec<Option<Geom>>, attributes: List, meta: &SfMeta) -> Result<Robj> {
+    let n = x.len();
+
+    let mut names = Vec::with_capacity(attributes.len() + 1);
+    let mut columns = Vec::with_capacity(attributes.len() + 1);
+
+    for (name, col) in attributes.iter() {
+        if col.len() != n {
+            return Err(Error::Other(format!(
+                "Column `{name}` has length {} but there are {n} geometries",
+                col.len()
+            )));
         }
+
+        names.push(name);
+        columns.push(col);
+    }
+
+    let agr = meta.agr_for(&names);
+
+    names.push(meta.sf_column.as_str());
+    columns.push(geoms_to_sfc_with_meta(x, &meta.sfc));
+
+    List::from_names_and_values(names, columns)?
+        .into_robj()
+        .set_attrib("row.names", (1..=n as i32).collect::<Vec<i32>>())?
+        .set_attrib("sf_column", meta.sf_column.as_str())?
+        .set_attrib("agr", agr)?
+        .set_class(["sf", "data.frame"])
+}
+
+/// Convert a `Rect` into an sf `bbox` object, a named numeric vector
+/// `c(xmin, ymin, xmax, ymax)` of class `bbox`. If a `Crs` is provided it is set as
+/// the `crs` attribute as `sf::st_bbox()` does.
+pub fn rect_to_bbox(x: Rect, crs: Option<Crs>) -> Robj {
+    let values = [x.min().x, x.min().y, x.max().x, x.max().y].map(Rfloat::from);
+    let bbox = new_bbox(values);
+
+    match crs {
+        Some(crs) => bbox.set_attrib("crs", crs).unwrap(),
+        None => bbox,
+    }
+}
+
+// the bbox attribute of an sfc object. It is NA if there are no non-empty geometries
+fn geoms_bbox(x: &[Option<Geom>]) -> Robj {
+    match union_bbox(x.iter().flatten()) {
+        Some(bbox) => rect_to_bbox(bbox, None),
+        None => new_bbox([Rfloat::na(); 4]),
+    }
+}
+
+fn new_bbox(values: [Rfloat; 4]) -> Robj {
+    Doubles::from_values(values)
+        .into_robj()
+        .set_attrib("names", vec!["xmin", "ymin", "xmax", "ymax"])
+        .unwrap()
+        .set_class(["bbox"])
+        .unwrap()
+}
+
+/// Utility function to identify the class of an sfc object. Returns the sf geometry
+/// type shared by every geometry e.g. `"POINT"`, `"GEOMETRY"` if they differ, or an
+/// empty string if there are none. `Line` is a `LINESTRING` and `Rect` and `Triangle`
+/// are `POLYGON`s as they are for `to_sfg()`.
+pub fn determine_sfc_class(x: &Vec<Option<Geom>>) -> String {
+    let mut result = "";
+    for geom in x.iter().flatten() {
+        let cls = sf_geometry_type(&geom.geom);
+        if result.is_empty() {
+            result = cls;
+        } else if result != cls {
+            result = "GEOMETRY";
+            break;
+        }
+    }
+    result.to_string()
+}
+
+// the geometry type of the sfg that `geometry_to_sfg()` creates
+fn sf_geometry_type(x: &Geometry) -> &'static str {
+    match crate::geometry_type(x) {
+        "LINE" => "LINESTRING",
+        "RECT" | "TRIANGLE" => "POLYGON",
+        cls => cls,
     }
-    result
 }
 
 
@@ -75,6 +294,46 @@ fn from_coord(x: Coord) -> [f64; 2] {
     [x.x, x.y]
 }
 
+/// Create a matrix from data that is already laid out in column-major order.
+/// Avoids calling a closure for every cell as `RMatrix::new_matrix()` does.
+pub fn new_matrix(x: Vec<f64>, nrow: usize, ncol: usize) -> Robj {
+    Robj::from(x)
+        .set_attrib("dim", vec![nrow as i32, ncol as i32])
+        .unwrap()
+}
+
+/// Create a matrix by calling `f(row, col)` for every cell, like
+/// `RMatrix::new_matrix()`. Kept for compatibility; prefer `new_matrix()` when the
+/// data can be laid out in column-major order directly.
+pub fn new_matrix_from_fn<F>(nrow: usize, ncol: usize, f: F) -> Robj
+where
+    F: Fn(usize, usize) -> f64,
+{
+    let mut data = Vec::with_capacity(nrow * ncol);
+
+    for c in 0..ncol {
+        for r in 0..nrow {
+            data.push(f(r, c));
+        }
+    }
+
+    new_matrix(data, nrow, ncol)
+}
+
+// lays out coordinates as the columns of an n x 2 matrix
+fn coords_to_matrix<'a>(x: impl ExactSizeIterator<Item = &'a Coord>) -> Robj {
+    let n = x.len();
+    let mut data = vec![0.0; n * 2];
+    let (xs, ys) = data.split_at_mut(n);
+
+    for ((xi, yi), crd) in xs.iter_mut().zip(ys.iter_mut()).zip(x) {
+        *xi = crd.x;
+        *yi = crd.y;
+    }
+
+    new_matrix(data, n, 2)
+}
+
 /// Convert a `Point` to a sfg
 pub fn from_point(x: Point) -> Robj {
     let x = from_coord(x.0);
@@ -85,13 +344,7 @@ pub fn from_point(x: Point) -> Robj {
 
 /// Convert a `MultiPoint` to an sfg
 pub fn from_multipoint(x: MultiPoint) -> Robj {
-    let x = x
-        .into_iter()
-        .map(|p| from_coord(p.into()))
-        .collect::<Vec<[f64; 2]>>();
-
-    let res = RMatrix::new_matrix(x.len(), 2, |r, c| x[r][c]);
-    Robj::from(res)
+    coords_to_matrix(x.0.iter().map(|p| &p.0))
         .set_class(["XY", "MULTIPOINT", "sfg"])
         .unwrap()
 }
@@ -99,10 +352,7 @@ pub fn from_multipoint(x: MultiPoint) -> Robj {
 
 /// Convert a `LineString` to an sfg
 pub fn from_linestring(x: LineString) -> Robj {
-    let x = x.into_iter().map(from_coord).collect::<Vec<[f64; 2]>>();
-
-    let res = RMatrix::new_matrix(x.len(), 2, |r, c| x[r][c]);
-    Robj::from(res)
+    coords_to_matrix(x.0.iter())
         .set_class(["XY", "LINESTRING", "sfg"])
         .unwrap()
 }
@@ -117,8 +367,16 @@ pub fn from_multilinestring(x: MultiLineString) -> Robj {
         .unwrap()
 }
 
-/// Convert a `Polygon` to an sfg
+/// Convert a `Polygon` to an sfg. A polygon with an empty exterior ring is
+/// converted to an empty `POLYGON` sfg i.e. an empty list.
 pub fn from_polygon(x: Polygon) -> Robj {
+    if x.exterior().0.is_empty() {
+        return List::new(0)
+            .into_robj()
+            .set_class(["XY", "POLYGON", "sfg"])
+            .unwrap();
+    }
+
     let exterior = x.exterior().to_owned();
     let interriors = x.interiors().to_owned();
 
@@ -146,3 +404,100 @@ pub fn from_multipolygon(x: MultiPolygon) -> Robj {
         .unwrap()
 }
 
+/// Convert a `GeometryCollection` to an sfg
+pub fn from_geometrycollection(x: GeometryCollection) -> Robj {
+    x.0.into_iter()
+        .map(geometry_to_sfg)
+        .collect::<List>()
+        .into_robj()
+        .set_class(["XY", "GEOMETRYCOLLECTION", "sfg"])
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_geometries_are_empty_sfg() {
+        test! {
+            let dim = |x: &Robj| x.dim().unwrap().iter().map(|d| d.inner()).collect::<Vec<i32>>();
+
+            let lns = to_sfg(Geom::from(LineString::new(vec![])));
+            assert_eq!(dim(&lns), vec![0, 2]);
+            assert!(lns.inherits("LINESTRING"));
+
+            let mpnt = to_sfg(Geom::from(MultiPoint::new(vec![])));
+            assert_eq!(dim(&mpnt), vec![0, 2]);
+
+            let poly = to_sfg(Geom::from(Polygon::new(LineString::new(vec![]), vec![])));
+            assert_eq!(poly.len(), 0);
+            assert!(poly.inherits("POLYGON"));
+        }
+    }
+
+    #[test]
+    fn coord_hook_is_applied_while_converting() {
+        test! {
+            let hook = Function::try_from(R!("function(m) m * 1000").unwrap()).unwrap();
+            let geoms = vec![Some(Geom::from(Point::new(1.0, 2.0))), None];
+
+            let sfc = geoms_to_sfc_with_hook(geoms, &hook).unwrap();
+
+            assert_eq!(sfc.elt(0).unwrap().as_real_slice(), Some(&[1000.0, 2000.0][..]));
+            assert!(sfc.elt(1).unwrap().is_null());
+        }
+    }
+
+    #[test]
+    fn closure_matrix_matches_column_major() {
+        test! {
+            let x = [[0.0, 10.0], [1.0, 11.0], [2.0, 12.0]];
+
+            let res = new_matrix_from_fn(3, 2, |r, c| x[r][c]);
+            let expected = Robj::from(RMatrix::new_matrix(3, 2, |r, c| x[r][c]));
+
+            assert_eq!(res.as_real_slice(), Some(&[0.0, 1.0, 2.0, 10.0, 11.0, 12.0][..]));
+            assert_eq!(res.as_real_slice(), expected.as_real_slice());
+            assert_eq!(res.dim(), expected.dim());
+        }
+    }
+
+    #[test]
+    fn sfc_class_of_primitives() {
+        let rect = Geom::from(Geometry::from(Rect::new((0.0, 0.0), (1.0, 1.0))));
+        let tri = Geom::from(Geometry::from(Triangle::from([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)])));
+        let line = Geom::from(Geometry::from(Line::new((0.0, 0.0), (1.0, 1.0))));
+        let poly = Geom::from(Rect::new((0.0, 0.0), (1.0, 1.0)).to_polygon());
+        let lns = Geom::from(LineString::from(vec![(0.0, 0.0), (1.0, 1.0)]));
+
+        assert_eq!(determine_sfc_class(&vec![Some(rect.clone()), None, Some(tri)]), "POLYGON");
+        assert_eq!(determine_sfc_class(&vec![Some(rect.clone()), Some(poly)]), "POLYGON");
+        assert_eq!(determine_sfc_class(&vec![Some(line), Some(lns)]), "LINESTRING");
+        assert_eq!(determine_sfc_class(&vec![Some(rect), Some(Geom::from(Point::new(0.0, 0.0)))]), "GEOMETRY");
+        assert_eq!(determine_sfc_class(&vec![None]), "");
+    }
+
+    #[test]
+    fn sfc_class_with_meta() {
+        test! {
+            let meta = SfcMeta::default();
+            let cls = |x: Vec<Option<Geom>>, meta: &SfcMeta| {
+                geoms_to_sfc_with_meta(x, meta).class().unwrap().map(String::from).collect::<Vec<String>>()
+            };
+
+            let rect = Geom::from(Geometry::from(Rect::new((0.0, 0.0), (1.0, 1.0))));
+            let poly = Geom::from(Polygon::new(LineString::new(vec![]), vec![]));
+            assert_eq!(cls(vec![Some(rect.clone()), Some(poly)], &meta), vec!["sfc_POLYGON", "sfc"]);
+
+            let gc = Geom::from(Geometry::from(GeometryCollection::new_from(vec![])));
+            assert_eq!(cls(vec![Some(gc)], &meta), vec!["sfc_GEOMETRYCOLLECTION", "sfc"]);
+            assert_eq!(cls(vec![Some(rect), Some(Geom::from(Point::new(0.0, 0.0)))], &meta), vec!["sfc_GEOMETRY", "sfc"]);
+
+            // the declared class is used when there are no geometries
+            assert_eq!(cls(vec![None], &meta), vec!["sfc_GEOMETRY", "sfc"]);
+            let meta = SfcMeta { class: Some("sfc_POINT".to_string()), ..SfcMeta::default() };
+            assert_eq!(cls(vec![], &meta), vec!["sfc_POINT", "sfc"]);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Write geometries as well-known text
+//!
+//! Geometries are written as WKT without any additional dependencies. `Line`s are
+//! written as `LINESTRING`s and `Rect`s and `Triangle`s as `POLYGON`s. Empty points,
+//! stored as `NaN` coordinates, are written as `POINT EMPTY`. Coordinates can be
+//! rounded to a number of decimal digits to produce compact WKT.
+use extendr_api::prelude::*;
+use geo_types::{Coord, Geometry, LineString, Polygon};
+use crate::Geom;
+
+/// Write a `Geom` as WKT. If `digits` is `Some(n)` coordinates are rounded to `n`
+/// decimal digits and trailing zeros are dropped, e.g. `1.50000001` is written as
+/// `1.5` with 3 digits. `None` writes coordinates at full precision.
+pub fn geom_to_wkt(x: &Geom, digits: Option<usize>) -> String {
+    let mut out = String::new();
+    write_geometry(&mut out, &x.geom, digits);
+    out
+}
+
+/// Write a `Vec<Option<Geom>>` as a character vector of WKT. Missing geometries are `NA`.
+pub fn geoms_to_wkt(x: Vec<Option<Geom>>, digits: Option<usize>) -> Robj {
+    x
+        .iter()
+        .map(|geom| geom.as_ref().map(|geom| geom_to_wkt(geom, digits)))
+        .collect::<Vec<Option<String>>>()
+        .into()
+}
+
+fn write_geometry(out: &mut String, x: &Geometry, digits: Option<usize>) {
+    match x {
+        Geometry::Point(x) => {
+            if x.x().is_nan() && x.y().is_nan() {
+                out.push_str("POINT EMPTY");
+            } else {
+                out.push_str("POINT (");
+                write_coord(out, &x.0, digits);
+                out.push(')');
+            }
+        }
+        Geometry::Line(x) => {
+            out.push_str("LINESTRING ");
+            write_coords(out, &[x.start, x.end], digits);
+        }
+        Geometry::LineString(x) => {
+            out.push_str("LINESTRING ");
+            write_coords(out, &x.0, digits);
+        }
+        Geometry::Polygon(x) => {
+            out.push_str("POLYGON ");
+            write_polygon(out, x, digits);
+        }
+        Geometry::Rect(x) => {
+            out.push_str("POLYGON ");
+            write_polygon(out, &x.to_polygon(), digits);
+        }
+        Geometry::Triangle(x) => {
+            out.push_str("POLYGON ");
+            write_polygon(out, &x.to_polygon(), digits);
+        }
+        Geometry::MultiPoint(x) => {
+            out.push_str("MULTIPOINT ");
+            let coords = x.iter().map(|pnt| pnt.0).collect::<Vec<Coord>>();
+            write_coords(out, &coords, digits);
+        }
+        Geometry::MultiLineString(x) => {
+            out.push_str("MULTILINESTRING ");
+            write_parts(out, &x.0, |out, lns: &LineString| write_coords(out, &lns.0, digits));
+        }
+        Geometry::MultiPolygon(x) => {
+            out.push_str("MULTIPOLYGON ");
+            write_parts(out, &x.0, |out, poly| write_polygon(out, poly, digits));
+        }
+        Geometry::GeometryCollection(x) => {
+            out.push_str("GEOMETRYCOLLECTION ");
+            write_parts(out, &x.0, |out, geom| write_geometry(out, geom, digits));
+        }
+    }
+}
+
+fn write_coord(out: &mut String, x: &Coord, digits: Option<usize>) {
+    write_number(out, x.x, digits);
+    out.push(' ');
+    write_number(out, x.y, digits);
+}
+
+fn write_number(out: &mut String, x: f64, digits: Option<usize>) {
+    let digits = match digits {
+        Some(digits) if x.is_finite() => digits,
+        _ => {
+            out.push_str(&x.to_string());
+            return;
+        }
+    };
+
+    let num = format!("{x:.digits$}");
+    let num = if num.contains('.') {
+        num.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &num
+    };
+
+    // avoid writing "-0" for small negative values rounded to zero
+    if num == "-0" {
+        out.push('0');
+    } else {
+        out.push_str(num);
+    }
+}
+
+fn write_coords(out: &mut String, x: &[Coord], digits: Option<usize>) {
+    write_parts(out, x, |out, crd| write_coord(out, crd, digits));
+}
+
+fn write_polygon(out: &mut String, x: &Polygon, digits: Option<usize>) {
+    if x.exterior().0.is_empty() {
+        out.push_str("EMPTY");
+        return;
+    }
+
+    let rings = std::iter::once(x.exterior())
+        .chain(x.interiors())
+        .collect::<Vec<&LineString>>();
+
+    write_parts(out, &rings, |out, ring: &&LineString| write_coords(out, &ring.0, digits));
+}
+
+// writes `(part, part, ...)` or `EMPTY` when there are no parts
+fn write_parts<T>(out: &mut String, x: &[T], write: impl Fn(&mut String, &T)) {
+    if x.is_empty() {
+        out.push_str("EMPTY");
+        return;
+    }
+
+    out.push('(');
+    for (i, part) in x.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write(out, part);
+    }
+    out.push(')');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::{MultiPoint, Point};
+
+    fn number(x: f64, digits: Option<usize>) -> String {
+        let mut out = String::new();
+        write_number(&mut out, x, digits);
+        out
+    }
+
+    #[test]
+    fn write_number_rounds_and_trims() {
+        assert_eq!(number(1.50000001, Some(3)), "1.5");
+        assert_eq!(number(2.0, Some(3)), "2");
+        assert_eq!(number(123.456, Some(0)), "123");
+        assert_eq!(number(-0.0001, Some(2)), "0");
+        assert_eq!(number(0.1 + 0.2, None), "0.30000000000000004");
+        assert_eq!(number(1e21, Some(2)), "1000000000000000000000");
+        assert_eq!(number(f64::NAN, Some(2)), "NaN");
+    }
+
+    #[test]
+    fn geometries_as_wkt() {
+        let pnt = Geom::from(Point::new(1.26, -3.0));
+        assert_eq!(geom_to_wkt(&pnt, None), "POINT (1.26 -3)");
+        assert_eq!(geom_to_wkt(&pnt, Some(1)), "POINT (1.3 -3)");
+
+        let empty = Geom::from(Point::new(f64::NAN, f64::NAN));
+        assert_eq!(geom_to_wkt(&empty, None), "POINT EMPTY");
+
+        let mpnt = Geom::from(MultiPoint::from(vec![(0.0, 0.0), (1.0, 1.0)]));
+        assert_eq!(geom_to_wkt(&mpnt, None), "MULTIPOINT (0 0, 1 1)");
+
+        let poly = Geom::from(Polygon::new(LineString::new(vec![]), vec![]));
+        assert_eq!(geom_to_wkt(&poly, None), "POLYGON EMPTY");
+    }
+}
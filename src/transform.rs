@@ -0,0 +1,53 @@
+//! Reproject geometries with PROJ
+//!
+//! Available with the `proj` feature which requires the PROJ system library.
+//! Coordinates are transformed using the [proj](https://docs.rs/proj) crate.
+use extendr_api::prelude::*;
+use geo::MapCoords;
+use proj::Proj;
+use crate::Geom;
+
+/// Transform every coordinate of a `Geom` from the `from` CRS to the `to` CRS.
+/// Both can be any CRS definition understood by PROJ such as `"EPSG:4326"` or
+/// a PROJ string. Returns an error if PROJ cannot create the transformation.
+pub fn geom_transform(x: Geom, from: &str, to: &str) -> Result<Geom> {
+    let proj = Proj::new_known_crs(from, to, None).map_err(|e| {
+        Error::Other(format!("Cannot create a transformation from `{from}` to `{to}`: {e}"))
+    })?;
+
+    let geom = x
+        .geom
+        .try_map_coords(|coord| proj.convert(coord))
+        .map_err(|e| Error::Other(format!("Failed to transform coordinates: {e}")))?;
+
+    Ok(Geom::from(geom))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::{Geometry, Point};
+
+    #[test]
+    fn lon_lat_to_web_mercator() {
+        let pnt = Geom::from(Point::new(10.0, 0.0));
+        let res = geom_transform(pnt, "EPSG:4326", "EPSG:3857").unwrap();
+
+        // x is the arc length along the equator, R * lon in radians
+        let expected = 6378137.0 * 10.0_f64.to_radians();
+        match res.geom {
+            Geometry::Point(pnt) => {
+                assert!((pnt.x() - expected).abs() < 1e-6, "{pnt:?}");
+                assert!(pnt.y().abs() < 1e-6, "{pnt:?}");
+            }
+            x => panic!("expected a POINT, found {x:?}"),
+        }
+    }
+
+    #[test]
+    fn unknown_crs_is_an_error() {
+        let pnt = Geom::from(Point::new(10.0, 0.0));
+        let err = geom_transform(pnt, "EPSG:4326", "not a crs").unwrap_err().to_string();
+        assert!(err.contains("Cannot create a transformation from `EPSG:4326` to `not a crs`"), "{err}");
+    }
+}
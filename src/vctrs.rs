@@ -11,15 +11,133 @@
 //! them accordingly. 
 use extendr_api::prelude::*;
 use extendr_api::List;
+use geo_types::{Geometry, LineString, MultiLineString, MultiPoint, MultiPolygon};
+use crate::{Geom, geometry_type, geoms_from_list};
 
 
-/// Converts a List of Geom pointers to a {vctrs} vctr
-pub fn as_rsgeo_vctr(x: List, class: &str) -> Robj {
+/// Converts a List of Geom pointers to a {vctrs} vctr. Returns an error if a
+/// non-NULL element does not have the `Geom` class.
+pub fn as_rsgeo_vctr(x: List, class: &str) -> Result<Robj> {
+    let invalid = x
+        .iter()
+        .position(|(_, robj)| !robj.is_null() && !robj.inherits("Geom"));
+
+    if let Some(i) = invalid {
+        return Err(Error::Other(format!(
+            "Element {} is not a `Geom` and cannot be part of an rsgeo vector",
+            i + 1
+        )));
+    }
+
+    x.set_class(geom_class(class))
+}
+
+/// Converts a `Geom` into a pointer with its scalar class e.g. `c("point", "Geom")`.
+/// rsgeo has no `Line`, `Rect`, or `Triangle` types so these become a `linestring`
+/// or `polygon` as they would in sf.
+pub fn geom_to_robj(x: Geom) -> Robj {
+    let x = Geom::from(rsgeo_geometry(x.geom));
+    let cls = geometry_type(&x.geom).to_lowercase();
     x
-        .set_class(geom_class(class))
+        .into_robj()
+        .set_class([cls.as_str(), "Geom"])
         .unwrap()
 }
 
+// converts the geo-types primitives without an rsgeo type
+fn rsgeo_geometry(x: Geometry) -> Geometry {
+    match x {
+        Geometry::Line(x) => LineString::new(vec![x.start, x.end]).into(),
+        Geometry::Rect(x) => x.to_polygon().into(),
+        Geometry::Triangle(x) => x.to_polygon().into(),
+        x => x,
+    }
+}
+
+/// Wrap a scalar `Geom` pointer such as `c("point", "Geom")` in a length 1 rsgeo
+/// vctr e.g. `rs_POINT`. Returns an error if `x` is not a `Geom`.
+pub fn geom_as_vctr(x: Robj) -> Result<Robj> {
+    if !x.inherits("Geom") {
+        return Err(Error::Other("`x` must be a `Geom`".to_string()));
+    }
+
+    let geom = <&Geom>::from_robj(&x).map_err(|e| Error::Other(e.to_string()))?;
+
+    // `Line`, `Rect`, and `Triangle` pointers are replaced, see `geom_to_robj()`
+    let x = match geom.geom {
+        Geometry::Line(_) | Geometry::Rect(_) | Geometry::Triangle(_) => geom_to_robj(geom.clone()),
+        _ => x,
+    };
+
+    let geom = <&Geom>::from_robj(&x).map_err(|e| Error::Other(e.to_string()))?;
+    let geom_type = geometry_type(&geom.geom).to_lowercase();
+    as_rsgeo_vctr(List::from_values([x]), &geom_type)
+}
+
+/// Deep copy a `Geom` into a new pointer with its scalar class. Copies of a pointer
+/// in R refer to the same `Geom` so modifying one in place modifies all of them.
+pub fn geom_clone(x: &Geom) -> Robj {
+    geom_to_robj(x.clone())
+}
+
+/// Converts a `Vec<Option<Geom>>` into a {vctrs} vctr. Missing geometries are
+/// stored as `NULL`. If the geometries are not all the same type the vctr is
+/// an `rs_GEOMETRYCOLLECTION`.
+pub fn geoms_to_rsgeo(x: Vec<Option<Geom>>) -> Robj {
+    let mut res = List::new(x.len());
+    let mut cls: Option<&str> = None;
+
+    for (i, geom) in x.into_iter().enumerate() {
+        if let Some(geom) = geom {
+            let geom = Geom::from(rsgeo_geometry(geom.geom));
+            let geom_type = geometry_type(&geom.geom);
+
+            cls = match cls {
+                Some(cls) if cls != geom_type => Some("geometrycollection"),
+                Some(cls) => Some(cls),
+                None => Some(geom_type),
+            };
+
+            res.set_elt(i, geom_to_robj(geom)).unwrap();
+        }
+    }
+
+    // every element is created by `geom_to_robj()` so has the `Geom` class
+    as_rsgeo_vctr(res, cls.unwrap_or("geometrycollection")).unwrap()
+}
+
+// the geometry types with an rsgeo vector class
+const GEOM_TYPES: [&str; 7] = [
+    "point",
+    "multipoint",
+    "linestring",
+    "multilinestring",
+    "polygon",
+    "multipolygon",
+    "geometrycollection",
+];
+
+/// The common length of named vectors under the vctrs recycling rules: each
+/// length must be 1 or equal the common length. Returns an error naming the two
+/// vectors with incompatible lengths.
+pub fn recycled_len_strict(x: &[(&str, usize)]) -> Result<usize> {
+    let common = x.iter().find(|(_, len)| *len != 1);
+
+    match common {
+        None => Ok(1),
+        Some((common_name, n)) => {
+            let incompatible = x.iter().find(|(_, len)| *len != 1 && len != n);
+
+            match incompatible {
+                Some((name, len)) => Err(Error::Other(format!(
+                    "Can't recycle `{name}` (size {len}) to match `{common_name}` (size {n})."
+                ))),
+                None => Ok(*n),
+            }
+        }
+    }
+}
+
 /// Create a `String` array of the vctrs class
 pub fn geom_class(cls: &str) -> [String; 4] {
     let cls = cls.to_uppercase();
@@ -29,25 +147,79 @@ pub fn geom_class(cls: &str) -> [String; 4] {
 }
 
 
-/// From a List, determine the {vctrs} class of the pointer list
+/// From a List, determine the {vctrs} class of the pointer list. Missing
+/// geometries are skipped. If there are no geometries or they are not all the
+/// same type the class is `rs_GEOMETRYCOLLECTION`.
 pub fn determine_geoms_class(x: &List) -> [String; 4] {
-
-    let class = x[0].class().unwrap().nth(0).unwrap();
-
-    let all_identical = x
+    let mut classes = x
         .iter()
-        .all(|(_, robj)| robj.class().unwrap().nth(0).unwrap() == class);
+        .filter(|(_, robj)| !robj.is_null())
+        .map(|(_, robj)| robj.class().and_then(|mut cls| cls.next()));
 
-    let class = if all_identical {
-        x[0].class().unwrap().nth(0).unwrap()
-    } else {
-        "geometrycollection"
+    let class = match classes.next() {
+        Some(Some(first)) if classes.all(|cls| cls == Some(first)) => first,
+        _ => "geometrycollection",
     };
 
     geom_class(class)
 }
 
 
+/// Check if all non-missing geometries of an rsgeo vector are the same type.
+/// An rsgeo vector with no geometries is homogeneous.
+pub fn rsgeo_is_homogeneous(x: List) -> bool {
+    let mut types = geoms_from_list(x)
+        .into_iter()
+        .flatten()
+        .map(|geom| geometry_type(&geom.geom));
+
+    match types.next() {
+        Some(first) => types.all(|geom_type| geom_type == first),
+        None => true,
+    }
+}
+
+/// Cast an rsgeo vector to the geometry type `to` e.g. `"multipoint"`. Geometries
+/// that are already of type `to` are unchanged and single geometries are up-cast
+/// to their multi counterpart. Returns an error for any other combination or if
+/// `to` is not an rsgeo geometry type.
+pub fn rsgeo_cast(x: List, to: &str) -> Result<Robj> {
+    let to = to.to_lowercase();
+
+    if !GEOM_TYPES.contains(&to.as_str()) {
+        return Err(Error::Other(format!(
+            "Cannot cast to `{to}`. `to` must be one of {}",
+            GEOM_TYPES.join(", ")
+        )));
+    }
+    let mut res = List::new(x.len());
+
+    for (i, geom) in geoms_from_list(x).into_iter().enumerate() {
+        if let Some(geom) = geom {
+            let from = geometry_type(&geom.geom).to_lowercase();
+
+            let cast: Geometry = match (geom.geom, to.as_str()) {
+                (geom, to) if from == to => geom,
+                (Geometry::Point(x), "multipoint") => MultiPoint::new(vec![x]).into(),
+                (Geometry::LineString(x), "multilinestring") => {
+                    MultiLineString::new(vec![x]).into()
+                }
+                (Geometry::Polygon(x), "multipolygon") => MultiPolygon::new(vec![x]).into(),
+                _ => {
+                    return Err(Error::Other(format!(
+                        "Cannot cast element {} from `{from}` to `{to}`",
+                        i + 1
+                    )))
+                }
+            };
+
+            res.set_elt(i, geom_to_robj(Geom::from(cast)))?;
+        }
+    }
+
+    as_rsgeo_vctr(res, &to)
+}
+
 /// Check if an object is an rsgeo vector 
 pub fn is_rsgeo(x: &List) -> Rbool {
     if x.is_null() {
@@ -66,19 +238,105 @@ pub fn verify_rsgeo(x: &List) {
     }
 }
 
-/// Returns the rsgeo vector type such as "point", "linestring", etc 
-pub fn rsgeo_type(x: &List) -> String {
+/// Returns the rsgeo vector type such as "point", "linestring", etc. The type is
+/// taken from the first class which must have the `rs_` prefix. Returns an error
+/// if `x` does not inherit from `rsgeo`.
+pub fn rsgeo_type(x: &List) -> Result<String> {
     if !x.inherits("rsgeo") {
-        panic!("object is not an `rsgeo` vector")
+        return Err(Error::Other("Object is not an `rsgeo` vector".to_string()));
     }
 
-    let cls = x.class().unwrap().next().unwrap();
+    let cls = x
+        .class()
+        .and_then(|mut cls| cls.next())
+        .ok_or_else(|| Error::Other("Object is not an `rsgeo` vector".to_string()))?;
 
-    if !cls.starts_with("rs_") {
-        panic!("Object is not an `rsgeo` vector with `rs_` prefix")
+    match cls.strip_prefix("rs_") {
+        Some(geom_type) => Ok(geom_type.to_lowercase()),
+        None => Err(Error::Other(
+            "Object is not an `rsgeo` vector with `rs_` prefix".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rsgeo_type_needs_the_rsgeo_class() {
+        test! {
+            let x = List::new(0);
+
+            let pnts = List::try_from(x.clone().set_class(geom_class("point")).unwrap()).unwrap();
+            assert_eq!(rsgeo_type(&pnts).unwrap(), "point");
+
+            let lns = List::try_from(x.clone().set_class(geom_class("LineString")).unwrap()).unwrap();
+            assert_eq!(rsgeo_type(&lns).unwrap(), "linestring");
+
+            // an `rs_` prefix alone is not an rsgeo vector
+            let prefixed = List::try_from(x.clone().set_class(["rs_POINT", "list"]).unwrap()).unwrap();
+            assert!(rsgeo_type(&prefixed).is_err());
+
+            let unprefixed = List::try_from(x.clone().set_class(["point", "rsgeo"]).unwrap()).unwrap();
+            assert!(rsgeo_type(&unprefixed).is_err());
+
+            assert!(rsgeo_type(&x).is_err());
+        }
+    }
+
+    #[test]
+    fn primitives_become_rsgeo_types() {
+        use geo_types::{Line, Polygon, Rect, Triangle};
+
+        test! {
+            let class = |x: &Robj| x.class().unwrap().collect::<Vec<&str>>();
+
+            let line = geom_to_robj(Geom::from(Line::new((0.0, 0.0), (1.0, 1.0))));
+            assert_eq!(class(&line), vec!["linestring", "Geom"]);
+
+            let rect = geom_to_robj(Geom::from(Rect::new((0.0, 0.0), (1.0, 1.0))));
+            assert_eq!(class(&rect), vec!["polygon", "Geom"]);
+            assert_eq!(
+                <&Geom>::from_robj(&rect).unwrap().geom,
+                Rect::new((0.0, 0.0), (1.0, 1.0)).to_polygon().into()
+            );
+
+            let tri = Geom::from(Geometry::Triangle(Triangle::from([(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)])));
+            assert_eq!(class(&geom_to_robj(tri.clone())), vec!["polygon", "Geom"]);
+
+            // a rect and a polygon are the same rsgeo type
+            let poly = Geom::from(Polygon::new(LineString::from(vec![(0.0, 0.0), (1.0, 0.0), (0.0, 0.0)]), vec![]));
+            let vctr = geoms_to_rsgeo(vec![Some(tri), Some(poly), None]);
+            assert_eq!(class(&vctr)[0], "rs_POLYGON");
+
+            let rect = Geom::from(Rect::new((0.0, 0.0), (1.0, 1.0))).into_robj().set_class(["rect", "Geom"]).unwrap();
+            assert_eq!(class(&geom_as_vctr(rect).unwrap())[0], "rs_POLYGON");
+        }
     }
 
-    let mut cls = cls.to_string();
-    cls.split_off(3).to_lowercase()
+    #[test]
+    fn cast_to_known_types_only() {
+        test! {
+            let missing = List::from_values([Robj::from(NULL), Robj::from(NULL)]);
+
+            let res = rsgeo_cast(missing.clone(), "MultiPoint").unwrap();
+            assert_eq!(res.class().unwrap().next(), Some("rs_MULTIPOINT"));
+
+            let err = rsgeo_cast(missing, "banana").unwrap_err().to_string();
+            assert!(err.contains("`banana`"));
+        }
+    }
+
+    #[test]
+    fn strict_recycling() {
+        assert_eq!(recycled_len_strict(&[("x", 3), ("dx", 1), ("dy", 3)]).unwrap(), 3);
+        assert_eq!(recycled_len_strict(&[("x", 1), ("dx", 1)]).unwrap(), 1);
+        assert_eq!(recycled_len_strict(&[("x", 0), ("dx", 1)]).unwrap(), 0);
+
+        let err = recycled_len_strict(&[("x", 3), ("dx", 2), ("dy", 1)]).unwrap_err();
+        assert_eq!(err.to_string(), "Can't recycle `dx` (size 2) to match `x` (size 3).");
+        assert!(recycled_len_strict(&[("x", 0), ("dx", 2)]).is_err());
+    }
 }
 
@@ -0,0 +1,44 @@
+//! Interoperability with the wk package
+//!
+//! wk provides lightweight geometry vectors: `wk_wkb`, a list of raw vectors, and
+//! `wk_wkt`, a character vector. These are the common currency of packages such as
+//! s2 and geos. These functions convert them to and from `Geom`s preserving the
+//! length and missingness of the vector. Reading `wk_wkt` requires the `wkt` feature.
+use extendr_api::prelude::*;
+use crate::{Geom, towkt::geoms_to_wkt, wkb::{geoms_to_wkb, wkb_to_geoms}};
+
+/// Convert a `wk_wkb` or `wk_wkt` vector into a `Vec<Option<Geom>>`.
+pub fn wk_to_geoms(x: Robj) -> Result<Vec<Option<Geom>>> {
+    if x.inherits("wk_wkb") {
+        wkb_to_geoms(List::try_from(x)?)
+    } else if x.inherits("wk_wkt") {
+        wk_wkt_to_geoms(x)
+    } else {
+        Err(Error::Other("`x` must be a `wk_wkb` or `wk_wkt` vector".to_string()))
+    }
+}
+
+#[cfg(feature = "wkt")]
+fn wk_wkt_to_geoms(x: Robj) -> Result<Vec<Option<Geom>>> {
+    crate::fromwkt::wkt_to_geoms(Strings::try_from(x)?)
+}
+
+#[cfg(not(feature = "wkt"))]
+fn wk_wkt_to_geoms(_x: Robj) -> Result<Vec<Option<Geom>>> {
+    Err(Error::Other("Reading `wk_wkt` vectors requires the `wkt` feature".to_string()))
+}
+
+/// Convert a `Vec<Option<Geom>>` into a `wk_wkb` vector.
+pub fn geoms_to_wk_wkb(x: Vec<Option<Geom>>) -> Robj {
+    geoms_to_wkb(x)
+        .into_robj()
+        .set_class(["wk_wkb", "wk_vctr"])
+        .unwrap()
+}
+
+/// Convert a `Vec<Option<Geom>>` into a `wk_wkt` vector.
+pub fn geoms_to_wk_wkt(x: Vec<Option<Geom>>) -> Robj {
+    geoms_to_wkt(x, None)
+        .set_class(["wk_wkt", "wk_vctr"])
+        .unwrap()
+}
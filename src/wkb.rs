@@ -0,0 +1,348 @@
+//! Read and write well-known binary
+//!
+//! `sf::st_as_binary()` and most database drivers return geometries as a list of
+//! raw vectors. These functions read ISO WKB in either byte order directly into
+//! `Geom`s without creating sfg objects. The simple feature types and
+//! `GEOMETRYCOLLECTION` are supported. Z and M coordinates, as either ISO
+//! (e.g. `1001`) or EWKB type codes, are dropped since `Geom`s are XY only. The
+//! SRID of EWKB is ignored. NULL elements are missing geometries. WKB is written
+//! in little endian byte order.
+use extendr_api::prelude::*;
+use geo_types::{
+    Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint,
+    MultiPolygon, Point, Polygon
+};
+use crate::{Geom, vctrs::geoms_to_rsgeo};
+
+/// Parse a list of raw vectors into a `Vec<Option<Geom>>`. Returns an error with
+/// the position of the first element that is not valid WKB.
+pub fn wkb_to_geoms(x: List) -> Result<Vec<Option<Geom>>> {
+    x.iter()
+        .enumerate()
+        .map(|(i, (_, robj))| {
+            if robj.is_null() {
+                return Ok(None);
+            }
+
+            let bytes = robj.as_raw_slice().ok_or_else(|| {
+                Error::Other(format!("Element {} is not a raw vector", i + 1))
+            })?;
+
+            wkb_to_geometry(bytes)
+                .map(|geom| Some(Geom::from(geom)))
+                .map_err(|e| Error::Other(format!("Invalid WKB at element {}: {e}", i + 1)))
+        })
+        .collect()
+}
+
+/// Parse a list of raw vectors into an rsgeo vctr.
+pub fn sfc_from_wkb(x: List) -> Result<Robj> {
+    Ok(geoms_to_rsgeo(wkb_to_geoms(x)?))
+}
+
+/// Parse a single WKB geometry.
+pub fn wkb_to_geometry(x: &[u8]) -> std::result::Result<Geometry, String> {
+    WkbReader { buf: x, pos: 0, little_endian: true, extra_dims: 0 }.read_geometry()
+}
+
+/// Write a `Vec<Option<Geom>>` as a list of raw vectors. Missing geometries are NULL.
+pub fn geoms_to_wkb(x: Vec<Option<Geom>>) -> List {
+    x
+        .into_iter()
+        .map(|geom| {
+            match geom {
+                Some(geom) => Raw::from_bytes(&geometry_to_wkb(&geom.geom)).into_robj(),
+                None => Robj::from(NULL),
+            }
+        }).collect::<List>()
+}
+
+/// Write a single geometry as WKB. `Line`s are written as linestrings and
+/// `Rect`s and `Triangle`s as polygons.
+pub fn geometry_to_wkb(x: &Geometry) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_geometry(&mut buf, x);
+    buf
+}
+
+fn write_geometry(buf: &mut Vec<u8>, x: &Geometry) {
+    match x {
+        Geometry::Point(x) => {
+            write_header(buf, 1);
+            write_coord(buf, &x.0);
+        }
+        Geometry::Line(x) => {
+            write_header(buf, 2);
+            write_coords(buf, &[x.start, x.end]);
+        }
+        Geometry::LineString(x) => {
+            write_header(buf, 2);
+            write_coords(buf, &x.0);
+        }
+        Geometry::Polygon(x) => write_polygon(buf, x),
+        Geometry::Rect(x) => write_polygon(buf, &x.to_polygon()),
+        Geometry::Triangle(x) => write_polygon(buf, &x.to_polygon()),
+        Geometry::MultiPoint(x) => {
+            write_header(buf, 4);
+            write_count(buf, x.0.len());
+            x.iter().for_each(|pnt| write_geometry(buf, &Geometry::Point(*pnt)));
+        }
+        Geometry::MultiLineString(x) => {
+            write_header(buf, 5);
+            write_count(buf, x.0.len());
+            x.iter().for_each(|lns| {
+                write_header(buf, 2);
+                write_coords(buf, &lns.0);
+            });
+        }
+        Geometry::MultiPolygon(x) => {
+            write_header(buf, 6);
+            write_count(buf, x.0.len());
+            x.iter().for_each(|poly| write_polygon(buf, poly));
+        }
+        Geometry::GeometryCollection(x) => {
+            write_header(buf, 7);
+            write_count(buf, x.0.len());
+            x.iter().for_each(|geom| write_geometry(buf, geom));
+        }
+    }
+}
+
+fn write_header(buf: &mut Vec<u8>, type_code: u32) {
+    buf.push(1);
+    buf.extend(type_code.to_le_bytes());
+}
+
+fn write_count(buf: &mut Vec<u8>, n: usize) {
+    buf.extend((n as u32).to_le_bytes());
+}
+
+fn write_coord(buf: &mut Vec<u8>, x: &Coord) {
+    buf.extend(x.x.to_le_bytes());
+    buf.extend(x.y.to_le_bytes());
+}
+
+fn write_coords(buf: &mut Vec<u8>, x: &[Coord]) {
+    write_count(buf, x.len());
+    x.iter().for_each(|crd| write_coord(buf, crd));
+}
+
+// an empty exterior ring is written as a polygon with no rings
+fn write_polygon(buf: &mut Vec<u8>, x: &Polygon) {
+    write_header(buf, 3);
+
+    if x.exterior().0.is_empty() {
+        write_count(buf, 0);
+        return;
+    }
+
+    write_count(buf, x.interiors().len() + 1);
+    write_coords(buf, &x.exterior().0);
+    x.interiors().iter().for_each(|ring| write_coords(buf, &ring.0));
+}
+
+struct WkbReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    little_endian: bool,
+    // the number of Z and M values that follow x and y in each coordinate
+    extra_dims: usize,
+}
+
+impl WkbReader<'_> {
+    fn read_bytes<const N: usize>(&mut self) -> std::result::Result<[u8; N], String> {
+        let bytes = self
+            .buf
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| format!("unexpected end of buffer at byte {}", self.pos))?;
+
+        self.pos += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn read_u32(&mut self) -> std::result::Result<u32, String> {
+        let bytes = self.read_bytes::<4>()?;
+        if self.little_endian {
+            Ok(u32::from_le_bytes(bytes))
+        } else {
+            Ok(u32::from_be_bytes(bytes))
+        }
+    }
+
+    fn read_f64(&mut self) -> std::result::Result<f64, String> {
+        let bytes = self.read_bytes::<8>()?;
+        if self.little_endian {
+            Ok(f64::from_le_bytes(bytes))
+        } else {
+            Ok(f64::from_be_bytes(bytes))
+        }
+    }
+
+    // the number of elements that follow. Each element needs at least
+    // `min_size` bytes which guards against allocating for corrupt counts
+    fn read_count(&mut self, min_size: usize) -> std::result::Result<usize, String> {
+        let n = self.read_u32()? as usize;
+        let remaining = self.buf.len() - self.pos;
+
+        if n * min_size > remaining {
+            return Err(format!("count of {n} at byte {} exceeds the buffer", self.pos - 4));
+        }
+
+        Ok(n)
+    }
+
+    fn read_coord(&mut self) -> std::result::Result<Coord, String> {
+        let crd = Coord { x: self.read_f64()?, y: self.read_f64()? };
+
+        for _ in 0..self.extra_dims {
+            self.read_f64()?;
+        }
+
+        Ok(crd)
+    }
+
+    fn read_linestring(&mut self) -> std::result::Result<LineString, String> {
+        let n = self.read_count(8 * (2 + self.extra_dims))?;
+        let coords = (0..n)
+            .map(|_| self.read_coord())
+            .collect::<std::result::Result<Vec<Coord>, String>>()?;
+
+        Ok(LineString::new(coords))
+    }
+
+    fn read_polygon(&mut self) -> std::result::Result<Polygon, String> {
+        let n = self.read_count(4)?;
+        let mut rings = (0..n)
+            .map(|_| self.read_linestring())
+            .collect::<std::result::Result<Vec<LineString>, String>>()?
+            .into_iter();
+
+        let exterior = rings.next().unwrap_or_else(|| LineString::new(vec![]));
+        Ok(Polygon::new(exterior, rings.collect()))
+    }
+
+    // reads the parts of a multi geometry which are complete WKB geometries
+    fn read_parts<T>(&mut self, expected: &str) -> std::result::Result<Vec<T>, String>
+    where
+        T: TryFrom<Geometry>,
+    {
+        let n = self.read_count(5)?;
+
+        (0..n)
+            .map(|_| {
+                let geom = self.read_geometry()?;
+                T::try_from(geom).map_err(|_| format!("expected {expected} parts"))
+            })
+            .collect()
+    }
+
+    fn read_geometry(&mut self) -> std::result::Result<Geometry, String> {
+        self.little_endian = match self.read_bytes::<1>()?[0] {
+            0 => false,
+            1 => true,
+            b => return Err(format!("invalid byte order {b} at byte {}", self.pos - 1)),
+        };
+
+        let code = self.read_u32()?;
+
+        // EWKB flags the dimensions and an SRID in the high bits
+        let has_z = code & 0x8000_0000 != 0;
+        let has_m = code & 0x4000_0000 != 0;
+
+        if code & 0x2000_0000 != 0 {
+            self.read_u32()?;
+        }
+
+        // ISO WKB adds 1000 for Z, 2000 for M, and 3000 for ZM
+        let code = code & 0x0fff_ffff;
+        let (code, iso_dims) = (code % 1000, code / 1000);
+
+        self.extra_dims = match (iso_dims, has_z, has_m) {
+            (0, false, false) => 0,
+            (0, true, true) | (3, false, false) => 2,
+            (0, _, _) | (1 | 2, false, false) => 1,
+            _ => return Err(format!("unsupported geometry type {}", code + iso_dims * 1000)),
+        };
+
+        let geom: Geometry = match code {
+            // an empty point is stored as NaN coordinates
+            1 => Point::from(self.read_coord()?).into(),
+            2 => self.read_linestring()?.into(),
+            3 => self.read_polygon()?.into(),
+            4 => MultiPoint::new(self.read_parts("POINT")?).into(),
+            5 => MultiLineString::new(self.read_parts("LINESTRING")?).into(),
+            6 => MultiPolygon::new(self.read_parts("POLYGON")?).into(),
+            7 => {
+                let n = self.read_count(5)?;
+                let geoms = (0..n)
+                    .map(|_| self.read_geometry())
+                    .collect::<std::result::Result<Vec<Geometry>, String>>()?;
+                GeometryCollection(geoms).into()
+            }
+            code => return Err(format!("unsupported geometry type {code}")),
+        };
+
+        Ok(geom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a little endian WKB header followed by `values`
+    fn wkb(code: u32, values: &[f64]) -> Vec<u8> {
+        let mut buf = vec![1];
+        buf.extend(code.to_le_bytes());
+        values.iter().for_each(|v| buf.extend(v.to_le_bytes()));
+        buf
+    }
+
+    #[test]
+    fn z_and_m_are_dropped() {
+        // `sf::st_as_binary(sf::st_point(c(1, 2, 3)))` and the XYM and XYZM variants
+        let expected = Geometry::from(Point::new(1.0, 2.0));
+        assert_eq!(wkb_to_geometry(&wkb(1001, &[1.0, 2.0, 3.0])).unwrap(), expected);
+        assert_eq!(wkb_to_geometry(&wkb(2001, &[1.0, 2.0, 4.0])).unwrap(), expected);
+        assert_eq!(wkb_to_geometry(&wkb(3001, &[1.0, 2.0, 3.0, 4.0])).unwrap(), expected);
+
+        // `sf::st_as_binary(sf::st_linestring(matrix(1:6, ncol = 3)))`
+        let mut lns = wkb(1002, &[]);
+        lns.extend(2u32.to_le_bytes());
+        [1.0, 3.0, 5.0, 2.0, 4.0, 6.0].iter().for_each(|v: &f64| lns.extend(v.to_le_bytes()));
+        assert_eq!(
+            wkb_to_geometry(&lns).unwrap(),
+            LineString::from(vec![(1.0, 3.0), (2.0, 4.0)]).into()
+        );
+
+        // `sf::st_as_binary(sf::st_sfc(sf::st_point(c(1, 2, 3)), crs = 4326), EWKB = TRUE)`
+        let mut ewkb = wkb(0xa000_0001, &[]);
+        ewkb.extend(4326u32.to_le_bytes());
+        [1.0, 2.0, 3.0].iter().for_each(|v: &f64| ewkb.extend(v.to_le_bytes()));
+        assert_eq!(wkb_to_geometry(&ewkb).unwrap(), expected);
+
+        // `sf::st_as_binary(sf::st_multipoint(matrix(1:6, ncol = 3), "XYZ"))`
+        let mut mpnt = wkb(1004, &[]);
+        mpnt.extend(2u32.to_le_bytes());
+        mpnt.extend(wkb(1001, &[1.0, 3.0, 5.0]));
+        mpnt.extend(wkb(1001, &[2.0, 4.0, 6.0]));
+        assert_eq!(
+            wkb_to_geometry(&mpnt).unwrap(),
+            MultiPoint::from(vec![(1.0, 3.0), (2.0, 4.0)]).into()
+        );
+    }
+
+    #[test]
+    fn round_trip_and_errors() {
+        let poly: Geometry = Polygon::new(
+            LineString::from(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)]),
+            vec![],
+        )
+        .into();
+
+        assert_eq!(wkb_to_geometry(&geometry_to_wkb(&poly)).unwrap(), poly);
+        assert!(wkb_to_geometry(&wkb(4001, &[1.0, 2.0])).is_err());
+        assert!(wkb_to_geometry(&wkb(1001, &[1.0, 2.0])).is_err());
+    }
+}
+